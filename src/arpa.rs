@@ -0,0 +1,243 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use crate::{data::History, Error, Result};
+
+/// Marks the beginning of a sentence.
+pub const SENTENCE_START: &str = "<s>";
+
+/// Marks the end of a sentence.
+pub const SENTENCE_END: &str = "</s>";
+
+/// Stands for words that are not in the model.
+pub const UNKNOWN: &str = "<unk>";
+
+/// Log10 probability ARPA files conventionally assign to impossible events, e.g. predicting the
+/// beginning of a sentence.
+pub const LOG_ZERO: f64 = -99.0;
+
+/// A unigram of an [`Arpa`] model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unigram {
+    /// Log10 probability of the word
+    pub logprob: f64,
+    /// Log10 backoff weight of the word as a context, absent for words that never precede another
+    /// word
+    pub backoff: Option<f64>,
+}
+
+/// A backoff unigram/bigram language model in the ARPA format, see [`History::to_arpa`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Arpa {
+    pub unigrams: BTreeMap<String, Unigram>,
+    /// Log10 probabilities of the second word following the first word
+    pub bigrams: BTreeMap<(String, String), f64>,
+}
+
+/// Estimates the absolute discount from the numbers of n-grams occurring once and twice, as
+/// proposed by Ney, Essen and Kneser, falling back to a half if there are too few of them.
+fn discount<'a, I>(counts: I) -> f64
+where
+    I: IntoIterator<Item = &'a usize>,
+{
+    let (mut n1, mut n2) = (0, 0);
+    for count in counts {
+        match count {
+            1 => n1 += 1,
+            2 => n2 += 1,
+            _ => {}
+        }
+    }
+    match n1 as f64 / (n1 + 2 * n2) as f64 {
+        d if d > 0.0 && d < 1.0 => d,
+        _ => 0.5,
+    }
+}
+
+impl History {
+    /// Builds a unigram/bigram language model from the sentences of this history (see
+    /// [`History::get_sentences`]), with absolute discounting and backoff to unigrams.
+    /// Sentences are wrapped in [`SENTENCE_START`] and [`SENTENCE_END`], and the probability mass
+    /// discounted from unigrams goes to [`UNKNOWN`].  Empty words are skipped.
+    ///
+    /// Fails if the history holds no words.
+    pub fn to_arpa(&self) -> Result<Arpa> {
+        let mut unigram_counts: HashMap<&str, usize> = HashMap::new();
+        let mut bigram_counts: HashMap<(&str, &str), usize> = HashMap::new();
+        let sentences = self.get_sentences();
+        for sentence in &sentences {
+            let words: Vec<&str> = std::iter::once(SENTENCE_START)
+                .chain(
+                    sentence
+                        .0
+                        .iter()
+                        .filter(|word| !word.is_empty())
+                        .map(|word| word.0.as_str()),
+                )
+                .chain(std::iter::once(SENTENCE_END))
+                .collect();
+            if words.len() == 2 {
+                continue;
+            }
+            for word in &words[1..] {
+                *unigram_counts.entry(word).or_default() += 1;
+            }
+            for pair in words.windows(2) {
+                *bigram_counts.entry((pair[0], pair[1])).or_default() += 1;
+            }
+        }
+        if unigram_counts.is_empty() {
+            return Err(Error::LogicError("The history holds no words".to_string()));
+        }
+
+        let total: usize = unigram_counts.values().sum();
+        let d1 = discount(unigram_counts.values());
+        let unigram_prob: HashMap<&str, f64> = unigram_counts
+            .iter()
+            .map(|(word, count)| (*word, (*count as f64 - d1) / total as f64))
+            .collect();
+        let unknown_prob = d1 * unigram_counts.len() as f64 / total as f64;
+
+        let d2 = discount(bigram_counts.values());
+        // Number of times each word precedes another word, and the number of distinct words that
+        // follow it.
+        let mut contexts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for ((prev, _), count) in &bigram_counts {
+            let context = contexts.entry(prev).or_default();
+            context.0 += count;
+            context.1 += 1;
+        }
+        let mut seen_unigram_mass: HashMap<&str, f64> = HashMap::new();
+        let mut ret = Arpa::default();
+        for ((prev, cur), count) in &bigram_counts {
+            let (context_count, _) = contexts[prev];
+            let prob = (*count as f64 - d2) / context_count as f64;
+            ret.bigrams
+                .insert((prev.to_string(), cur.to_string()), prob.log10());
+            *seen_unigram_mass.entry(prev).or_default() += unigram_prob[cur];
+        }
+
+        let backoff = |word: &str| {
+            contexts.get(word).map(|(count, types)| {
+                let left = d2 * *types as f64 / *count as f64;
+                (left / (1.0 - seen_unigram_mass[word])).log10()
+            })
+        };
+        ret.unigrams.insert(
+            UNKNOWN.to_string(),
+            Unigram {
+                logprob: unknown_prob.log10(),
+                backoff: None,
+            },
+        );
+        ret.unigrams.insert(
+            SENTENCE_START.to_string(),
+            Unigram {
+                logprob: LOG_ZERO,
+                backoff: backoff(SENTENCE_START),
+            },
+        );
+        for (word, prob) in &unigram_prob {
+            ret.unigrams.insert(
+                word.to_string(),
+                Unigram {
+                    logprob: prob.log10(),
+                    backoff: backoff(word),
+                },
+            );
+        }
+        Ok(ret)
+    }
+}
+
+impl Arpa {
+    /// Log10 probability of the word `cur` following the word `prev`, backing off to the
+    /// probability of `cur` if the model has no such bigram.  Words that are not in the model
+    /// are treated as [`UNKNOWN`].
+    pub fn score(&self, prev: &str, cur: &str) -> f64 {
+        let known = |word: &str| match self.unigrams.contains_key(word) {
+            true => word.to_string(),
+            false => UNKNOWN.to_string(),
+        };
+        let (prev, cur) = (known(prev), known(cur));
+        if let Some(logprob) = self.bigrams.get(&(prev.clone(), cur.clone())) {
+            return *logprob;
+        }
+        let backoff = self.unigrams[&prev].backoff.unwrap_or(0.0);
+        backoff + self.unigrams[&cur].logprob
+    }
+}
+
+impl Display for Arpa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "\\data\\")?;
+        writeln!(f, "ngram 1={}", self.unigrams.len())?;
+        writeln!(f, "ngram 2={}", self.bigrams.len())?;
+        writeln!(f)?;
+        writeln!(f, "\\1-grams:")?;
+        for (word, unigram) in &self.unigrams {
+            match unigram.backoff {
+                Some(backoff) => writeln!(f, "{:.6}\t{}\t{:.6}", unigram.logprob, word, backoff)?,
+                None => writeln!(f, "{:.6}\t{}", unigram.logprob, word)?,
+            }
+        }
+        writeln!(f)?;
+        writeln!(f, "\\2-grams:")?;
+        for ((prev, cur), logprob) in &self.bigrams {
+            writeln!(f, "{:.6}\t{} {}", logprob, prev, cur)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "\\end\\")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{LOG_ZERO, SENTENCE_END, SENTENCE_START, UNKNOWN};
+    use crate::{data::History, test_util::history, Result};
+
+    fn sample() -> History {
+        history(vec![vec!["我 喜欢 音乐", "我 喜欢 你"], vec!["音乐 好听"]])
+    }
+
+    #[test]
+    fn probabilities_sum_to_one() -> Result<()> {
+        let arpa = sample().to_arpa()?;
+        assert_eq!(arpa.unigrams[SENTENCE_START].logprob, LOG_ZERO);
+        let total: f64 = arpa
+            .unigrams
+            .iter()
+            .filter(|(word, _)| *word != SENTENCE_START)
+            .map(|(_, unigram)| 10f64.powf(unigram.logprob))
+            .sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        for prev in [SENTENCE_START, "我", "喜欢", "音乐", "好听", "你"] {
+            let total: f64 = arpa
+                .unigrams
+                .keys()
+                .filter(|cur| *cur != SENTENCE_START)
+                .map(|cur| 10f64.powf(arpa.score(prev, cur)))
+                .sum();
+            assert!((total - 1.0).abs() < 1e-9, "{}: {}", prev, total);
+        }
+        assert!(arpa.score("我", "喜欢") > arpa.score("我", "音乐"));
+        assert_eq!(arpa.score("陌生", "词"), arpa.score(UNKNOWN, UNKNOWN));
+        Ok(())
+    }
+
+    #[test]
+    fn format() -> Result<()> {
+        let arpa = history(vec![vec!["你好"]]).to_arpa()?;
+        let text = arpa.to_string();
+        assert!(text.starts_with("\\data\\\nngram 1=4\nngram 2=2\n\n\\1-grams:\n"));
+        assert!(text.contains(&format!("\t{} 你好\n", SENTENCE_START)));
+        assert!(text.contains(&format!("\t你好 {}\n", SENTENCE_END)));
+        assert!(text.ends_with("\n\\end\\\n"));
+        assert!(History::default().to_arpa().is_err());
+        Ok(())
+    }
+}
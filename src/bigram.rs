@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use crate::data::{History, Pool};
+
+/// Each pool's counts weigh this much relative to those of the previous (newer) pool.
+pub const POOL_DECAY: f32 = 0.5;
+
+/// Share of the bigram probability when interpolating it with the unigram probability in
+/// [`HistoryBigram::score`].
+pub const BIGRAM_WEIGHT: f32 = 0.68;
+
+/// Probability assigned to transitions that never occur in the history.
+pub const UNKNOWN_PENALTY: f32 = 1.0 / 60000000.0;
+
+/// Unigram and bigram counts of the sentences inside one [`Pool`].
+#[derive(Clone, Debug, Default, PartialEq)]
+struct BigramPool {
+    unigram: HashMap<String, u32>,
+    /// Maps a word to the words that follow it, with the number of times they do
+    bigram: HashMap<String, HashMap<String, u32>>,
+    /// Number of words inside the pool
+    size: usize,
+}
+
+impl From<&Pool> for BigramPool {
+    fn from(pool: &Pool) -> Self {
+        let mut ret = BigramPool::default();
+        for sentence in &pool.0 {
+            let words: Vec<&str> = sentence
+                .0
+                .iter()
+                .filter(|word| !word.is_empty())
+                .map(|word| word.0.as_str())
+                .collect();
+            ret.size += words.len();
+            for word in &words {
+                *ret.unigram.entry(word.to_string()).or_default() += 1;
+            }
+            for pair in words.windows(2) {
+                *ret.bigram
+                    .entry(pair[0].to_string())
+                    .or_default()
+                    .entry(pair[1].to_string())
+                    .or_default() += 1;
+            }
+        }
+        ret
+    }
+}
+
+impl BigramPool {
+    fn unigram_freq(&self, word: &str) -> u32 {
+        self.unigram.get(word).copied().unwrap_or_default()
+    }
+
+    fn bigram_freq(&self, prev: &str, cur: &str) -> u32 {
+        self.bigram
+            .get(prev)
+            .and_then(|next| next.get(cur))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// The user language model [`libime`][libime] derives from its history: every pool contributes
+/// its unigram and bigram counts, scaled by a weight that decays from the newest pool to the
+/// oldest one.  The weighted counts bias the ranking of candidates towards what the user typed
+/// recently.
+///
+/// REF: <https://github.com/fcitx/libime/blob/2e90224d4905c9228c4008bca52155829d673532/src/libime/core/historybigram.cpp>
+///
+/// [libime]: https://github.com/fcitx/libime
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryBigram {
+    pools: Vec<BigramPool>,
+    /// Weight of each pool's counts, newest pool first
+    pub pool_weights: Vec<f32>,
+    /// See [`BIGRAM_WEIGHT`]
+    pub bigram_weight: f32,
+    /// See [`UNKNOWN_PENALTY`]
+    pub unknown_penalty: f32,
+}
+
+impl From<&History> for HistoryBigram {
+    fn from(history: &History) -> Self {
+        let decayed: Vec<f32> = (0..history.pools.len())
+            .map(|i| POOL_DECAY.powi(i as i32))
+            .collect();
+        let total: f32 = decayed.iter().sum();
+        HistoryBigram {
+            pools: history.pools.iter().map(BigramPool::from).collect(),
+            pool_weights: decayed.into_iter().map(|weight| weight / total).collect(),
+            bigram_weight: BIGRAM_WEIGHT,
+            unknown_penalty: UNKNOWN_PENALTY,
+        }
+    }
+}
+
+impl HistoryBigram {
+    /// Builds the model of `history`, same as `HistoryBigram::from(history)`.
+    pub fn new(history: &History) -> Self {
+        HistoryBigram::from(history)
+    }
+
+    /// Sums `f(pool)` over all pools, scaled by the pools' weights.
+    fn weighted<F>(&self, f: F) -> f32
+    where
+        F: Fn(&BigramPool) -> f32,
+    {
+        self.pools
+            .iter()
+            .zip(&self.pool_weights)
+            .map(|(pool, weight)| f(pool) * weight)
+            .sum()
+    }
+
+    /// Weighted number of occurrences of `word`.
+    pub fn unigram_freq(&self, word: &str) -> f32 {
+        self.weighted(|pool| pool.unigram_freq(word) as f32)
+    }
+
+    /// Weighted number of times `cur` directly follows `prev`.
+    pub fn bigram_freq(&self, prev: &str, cur: &str) -> f32 {
+        self.weighted(|pool| pool.bigram_freq(prev, cur) as f32)
+    }
+
+    /// Weighted number of words in the history.
+    pub fn unigram_size(&self) -> f32 {
+        self.weighted(|pool| pool.size as f32)
+    }
+
+    /// Checks if `word` never occurs in the history.
+    pub fn is_unknown(&self, word: &str) -> bool {
+        self.pools.iter().all(|pool| pool.unigram_freq(word) == 0)
+    }
+
+    /// Words that directly follow `prev` somewhere in the history, with their weighted counts,
+    /// most frequent first.
+    pub fn successors(&self, prev: &str) -> Vec<(String, f32)> {
+        let mut ret: HashMap<&str, f32> = HashMap::new();
+        for (pool, weight) in self.pools.iter().zip(&self.pool_weights) {
+            for (cur, count) in pool.bigram.get(prev).into_iter().flatten() {
+                *ret.entry(cur).or_default() += *count as f32 * weight;
+            }
+        }
+        let mut ret: Vec<(String, f32)> = ret
+            .into_iter()
+            .map(|(cur, freq)| (cur.to_string(), freq))
+            .collect();
+        ret.sort_by(|lhs, rhs| rhs.1.total_cmp(&lhs.1).then_with(|| lhs.0.cmp(&rhs.0)));
+        ret
+    }
+
+    /// All pairs of adjacent words in the history.
+    pub fn bigrams(&self) -> Vec<(String, String)> {
+        let mut ret: Vec<(String, String)> = self
+            .pools
+            .iter()
+            .flat_map(|pool| {
+                pool.bigram.iter().flat_map(|(prev, next)| {
+                    next.keys()
+                        .map(move |cur| (prev.to_string(), cur.to_string()))
+                })
+            })
+            .collect();
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+
+    /// All distinct words in the history.
+    pub fn words(&self) -> Vec<String> {
+        let mut ret: Vec<String> = self
+            .pools
+            .iter()
+            .flat_map(|pool| pool.unigram.keys().cloned())
+            .collect();
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+
+    /// Log10 probability of `cur` following `prev`, interpolating the bigram probability
+    /// `bigram_freq(prev, cur) / unigram_freq(prev)` with the unigram probability
+    /// `unigram_freq(cur) / unigram_size()`.  A half is added to the denominators to avoid
+    /// division by zero.  Transitions that never occur score `log10(unknown_penalty)`.
+    pub fn score(&self, prev: &str, cur: &str) -> f32 {
+        let uf0 = self.unigram_freq(prev);
+        let bf = self.bigram_freq(prev, cur);
+        let uf1 = self.unigram_freq(cur);
+
+        let pr = self.bigram_weight * bf / (uf0 + 0.5)
+            + (1.0 - self.bigram_weight) * uf1 / (self.unigram_size() + 0.5);
+        if pr <= 0.0 {
+            self.unknown_penalty.log10()
+        } else {
+            pr.min(1.0).log10()
+        }
+    }
+}
+
+impl History {
+    /// Builds the user language model [`libime`][libime] derives from this history, see
+    /// [`HistoryBigram`].
+    ///
+    /// [libime]: https://github.com/fcitx/libime
+    pub fn bigram(&self) -> HistoryBigram {
+        HistoryBigram::from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::UNKNOWN_PENALTY;
+    use crate::{data::History, test_util::history};
+
+    fn assert_close(lhs: f32, rhs: f32) {
+        assert!((lhs - rhs).abs() < 1e-6, "{} != {}", lhs, rhs);
+    }
+
+    fn sample() -> History {
+        history(vec![
+            vec!["音乐 好听"],
+            vec!["音乐 好听 吗", "音乐 难听"],
+            vec!["音乐 难听"],
+        ])
+    }
+
+    #[test]
+    fn weighted_counts() {
+        let model = sample().bigram();
+        assert_eq!(model.pool_weights, vec![4.0 / 7.0, 2.0 / 7.0, 1.0 / 7.0]);
+        assert_close(model.unigram_freq("音乐"), (4.0 + 2.0 * 2.0 + 1.0) / 7.0);
+        assert_close(model.bigram_freq("音乐", "好听"), (4.0 + 2.0) / 7.0);
+        assert_close(model.bigram_freq("音乐", "难听"), (2.0 + 1.0) / 7.0);
+        assert_close(model.unigram_size(), (4.0 * 2.0 + 2.0 * 5.0 + 2.0) / 7.0);
+        assert!(model.is_unknown("你好"));
+        let successors = model.successors("音乐");
+        assert_eq!(
+            successors
+                .iter()
+                .map(|(cur, _)| cur.as_str())
+                .collect::<Vec<_>>(),
+            vec!["好听", "难听"]
+        );
+        assert_close(successors[0].1, 6.0 / 7.0);
+        assert_close(successors[1].1, 3.0 / 7.0);
+    }
+
+    #[test]
+    fn score() {
+        let model = sample().bigram();
+        assert!(model.score("音乐", "好听") > model.score("音乐", "难听"));
+        assert!(model.score("音乐", "好听") > model.score("吗", "好听"));
+        assert!(model.score("音乐", "好听") <= 0.0);
+        assert_eq!(model.score("你好", "世界"), UNKNOWN_PENALTY.log10());
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    data::History,
+    filter::Filter,
+    search::{Pattern, Scope},
+    utils::xdg_dir,
+    Error, Result,
+};
+
+/// Words, phrases and regular expressions whose sentences must never make it into a history.
+///
+/// A blocklist file holds one entry per line:
+///
+/// ```text
+/// # Lines starting with "#" and empty lines are ignored.
+/// 张三
+/// 某某 项目
+/// /^内部.*代号$/
+/// ```
+///
+/// A line without spaces blocks sentences containing that word, a line with spaces blocks
+/// sentences containing those words next to each other, and a line enclosed in slashes is a
+/// regular expression that blocks sentences it matches, where sentences are rendered with their
+/// words separated by spaces.
+///
+/// A blocklist is applied to a history with [`Blocklist::apply`], or to histories being merged
+/// with [`merge_with`](crate::merge_with).
+#[derive(Clone, Debug, Default)]
+pub struct Blocklist {
+    filter: Filter,
+}
+
+impl Blocklist {
+    /// Returns where the blocklist lives by default,
+    /// `$XDG_CONFIG_HOME/libime-history-merge/blocklist`, where `$XDG_CONFIG_HOME` defaults to
+    /// `~/.config`.
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(xdg_dir("XDG_CONFIG_HOME", ".config")?
+            .join(env!("CARGO_PKG_NAME"))
+            .join("blocklist"))
+    }
+
+    /// Parses the content of a blocklist file.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut filter = Filter::default();
+        for (n, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(re) = line
+                .strip_prefix('/')
+                .and_then(|line| line.strip_suffix('/'))
+            {
+                filter.patterns.push(
+                    Pattern::regex(re, Scope::Sentence)
+                        .map_err(|e| Error::DeserializeError(format!("Line {}: {}", n + 1, e)))?,
+                );
+            } else if line.contains(char::is_whitespace) {
+                let phrase: Vec<String> = line.split_whitespace().map(regex::escape).collect();
+                filter.patterns.push(Pattern::regex(
+                    &format!("(?:^| ){}(?: |$)", phrase.join(" ")),
+                    Scope::Sentence,
+                )?);
+            } else {
+                filter.words.insert(line.to_string());
+            }
+        }
+        Ok(Blocklist { filter })
+    }
+
+    pub fn load<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let content = std::fs::read_to_string(&p)?;
+        Blocklist::parse(&content).map_err(|e| {
+            Error::DeserializeError(format!(
+                "Could not load blocklist from path '{}': {}",
+                p.as_ref().display(),
+                e,
+            ))
+        })
+    }
+
+    /// Loads the blocklist at [`Blocklist::default_path`], if there is one.
+    pub fn load_default() -> Result<Option<Self>> {
+        let path = Blocklist::default_path()?;
+        match path.exists() {
+            true => Ok(Some(Blocklist::load(path)?)),
+            false => Ok(None),
+        }
+    }
+
+    /// Checks if the blocklist has no entries, thus blocks nothing.
+    pub fn is_empty(&self) -> bool {
+        self.filter.is_empty()
+    }
+
+    /// Removes the blocked sentences from `history`, and returns how many were removed.
+    pub fn apply(&self, history: &mut History) -> usize {
+        history.remove_matching(&self.filter).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::Blocklist;
+    use crate::{
+        test_util::{history, sentence},
+        Result,
+    };
+
+    #[test]
+    fn entries() -> Result<()> {
+        let blocklist = Blocklist::parse("# comment\n\n张三\n某某 项目\n  /^内部.*代号$/  \n")?;
+        let mut history = history(vec![vec![
+            "我 和 张三",
+            "张三丰",
+            "某某 项目 进展",
+            "某某 的 项目",
+            "内部 的 代号",
+            "内部 代号 是",
+        ]]);
+        assert_eq!(blocklist.apply(&mut history), 3);
+        assert_eq!(
+            history.get_sentences(),
+            vec![
+                sentence("张三丰"),
+                sentence("某某 的 项目"),
+                sentence("内部 代号 是")
+            ]
+        );
+        assert!(Blocklist::parse("# nothing\n")?.is_empty());
+        assert!(Blocklist::parse("/(/").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn merge_with() -> Result<()> {
+        let blocklist = Blocklist::parse("屏蔽词")?;
+        let (merged, dropped) = crate::merge_with(
+            vec![
+                history(vec![vec!["屏蔽词 测试", "你好"]]),
+                history(vec![vec!["屏蔽词"]]),
+            ],
+            Vec::new(),
+            &blocklist,
+        )?;
+        assert_eq!(merged.get_sentences(), vec![sentence("你好")]);
+        assert_eq!(dropped, 2);
+        Ok(())
+    }
+}
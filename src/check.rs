@@ -0,0 +1,469 @@
+use std::{borrow::Cow, fmt::Display};
+
+use crate::{
+    data::History,
+    data_bytes::{decompress_pools, FORMAT_VERSION_V2, FORMAT_VERSION_V3, MAGIC},
+    to_bytes,
+    walk::{walk, Field, Source},
+    wrapper::Wrapper,
+    BytesDeserializer,
+};
+
+/// Names of the checks [`check`] runs, in order.
+pub const CHECKS: [&str; 7] = [
+    "wrapper",
+    "magic",
+    "version",
+    "decompression",
+    "structure",
+    "utf-8",
+    "round trip",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    /// The check does not apply, or cannot run because of an earlier failure
+    Skip,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Outcome::Pass => "PASS",
+            Outcome::Fail => "FAIL",
+            Outcome::Skip => "SKIP",
+        })
+    }
+}
+
+/// Result of one of the [`CHECKS`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+/// Results of all [`CHECKS`] on a binary history, see [`check`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Checks if no check failed.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.outcome != Outcome::Fail)
+    }
+
+    /// Returns the result of the check named `name`, if it ran.
+    pub fn get(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    fn push<S: Into<String>>(&mut self, name: &'static str, outcome: Outcome, detail: S) {
+        self.checks.push(Check {
+            name,
+            outcome,
+            detail: detail.into(),
+        });
+    }
+
+    /// Marks the checks that have not run as skipped because of an earlier failure.
+    fn finish(mut self) -> Self {
+        for name in CHECKS.iter().skip(self.checks.len()) {
+            self.push(name, Outcome::Skip, "an earlier check failed");
+        }
+        self
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{}  {:<13}  {}", check.outcome, check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pools of a history in memory, checking that their words are valid UTF-8 as they are read.
+struct Words<'a> {
+    de: BytesDeserializer<'a>,
+    len: usize,
+    /// Number of words read
+    words: usize,
+    /// Offsets of the words that are not valid UTF-8
+    invalid: Vec<usize>,
+}
+
+impl Source for Words<'_> {
+    fn offset(&self) -> u64 {
+        (self.len - self.de.remaining_len()) as u64
+    }
+
+    fn u32(&mut self, _: Field) -> std::io::Result<Option<u32>> {
+        Ok(self.de.parse_u32().ok())
+    }
+
+    fn bytes(&mut self, _: Field, len: u32) -> std::io::Result<bool> {
+        let offset = self.offset() as usize;
+        let Ok(word) = self.de.next_exact_bytes(len as usize) else {
+            return Ok(false);
+        };
+        self.words += 1;
+        if std::str::from_utf8(word).is_err() {
+            self.invalid.push(offset);
+        }
+        Ok(true)
+    }
+
+    fn rest(&mut self) -> std::io::Result<u64> {
+        Ok(self.de.remaining_len() as u64)
+    }
+}
+
+/// Checks that `content` is a well-formed binary history that [`libime`][libime] can load, and
+/// that it survives a round trip through [`History::load_from_bytes`] and [`to_bytes`]
+/// unchanged.  A file wrapped in general-purpose compression (see [`Wrapper`]) is unwrapped
+/// first.  Each of the [`CHECKS`] is reported separately.
+///
+/// [libime]: https://github.com/fcitx/libime
+pub fn check(content: &[u8]) -> Report {
+    let mut report = Report::default();
+    let unwrapped = match Wrapper::detect(content) {
+        Some(wrapper) => match wrapper.unwrap(content) {
+            Ok(unwrapped) => {
+                report.push(
+                    "wrapper",
+                    Outcome::Pass,
+                    format!(
+                        "{}, offsets are relative to the {} unwrapped byte(s)",
+                        wrapper,
+                        unwrapped.len()
+                    ),
+                );
+                Cow::Owned(unwrapped)
+            }
+            Err(e) => {
+                report.push("wrapper", Outcome::Fail, format!("{}: {}", wrapper, e));
+                return report.finish();
+            }
+        },
+        None => {
+            report.push("wrapper", Outcome::Skip, "not wrapped");
+            Cow::Borrowed(content)
+        }
+    };
+    let content = &unwrapped[..];
+    let mut de = BytesDeserializer::new(content);
+
+    match de.parse_u32() {
+        Ok(MAGIC) => report.push("magic", Outcome::Pass, format!("0x{:08x}", MAGIC)),
+        Ok(magic) => {
+            report.push(
+                "magic",
+                Outcome::Fail,
+                format!("expected 0x{:08x}, got 0x{:08x}", MAGIC, magic),
+            );
+            return report.finish();
+        }
+        Err(_) => {
+            report.push("magic", Outcome::Fail, "file is shorter than 4 bytes");
+            return report.finish();
+        }
+    }
+
+    let version = match de.parse_u32() {
+        Ok(version @ (FORMAT_VERSION_V2 | FORMAT_VERSION_V3)) => {
+            report.push("version", Outcome::Pass, version.to_string());
+            version
+        }
+        Ok(version) => {
+            report.push(
+                "version",
+                Outcome::Fail,
+                format!(
+                    "unsupported version {}, expected {} or {}",
+                    version, FORMAT_VERSION_V2, FORMAT_VERSION_V3
+                ),
+            );
+            return report.finish();
+        }
+        Err(_) => {
+            report.push("version", Outcome::Fail, "file ends inside the header");
+            return report.finish();
+        }
+    };
+
+    let body = &content[content.len() - de.remaining_len()..];
+    let payload: Cow<[u8]> = if version == FORMAT_VERSION_V3 {
+        match decompress_pools(body) {
+            Ok(payload) => {
+                report.push(
+                    "decompression",
+                    Outcome::Pass,
+                    format!(
+                        "{} compressed byte(s), {} decompressed",
+                        body.len(),
+                        payload.len()
+                    ),
+                );
+                Cow::Owned(payload)
+            }
+            Err(e) => {
+                report.push("decompression", Outcome::Fail, format!("zstd: {}", e));
+                return report.finish();
+            }
+        }
+    } else {
+        report.push(
+            "decompression",
+            Outcome::Skip,
+            "version 2 is not compressed",
+        );
+        Cow::Borrowed(body)
+    };
+
+    let header_len = content.len() - body.len();
+    let describe = |offset: usize| match version {
+        FORMAT_VERSION_V3 => format!("decompressed offset 0x{:x}", offset),
+        _ => format!("offset 0x{:x}", header_len + offset),
+    };
+    let mut words = Words {
+        de: BytesDeserializer::new(&payload),
+        len: payload.len(),
+        words: 0,
+        invalid: Vec::new(),
+    };
+    let walk = walk(&mut words).expect("reading from memory does not fail");
+    match (&walk.end, walk.trailing) {
+        (Some(end), _) => report.push(
+            "structure",
+            Outcome::Fail,
+            format!("{} ({})", end, describe(end.offset as usize)),
+        ),
+        (None, 0) => report.push(
+            "structure",
+            Outcome::Pass,
+            format!(
+                "{} pools of {} sentence(s)",
+                walk.pools.len(),
+                walk.pools
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            ),
+        ),
+        (None, trailing) => report.push(
+            "structure",
+            Outcome::Fail,
+            format!(
+                "{} unexpected byte(s) after the last pool, from {}",
+                trailing,
+                describe(payload.len() - trailing as usize)
+            ),
+        ),
+    }
+    match words.invalid.first() {
+        Some(&first) => report.push(
+            "utf-8",
+            Outcome::Fail,
+            format!(
+                "{} word(s) are not valid UTF-8, the first one at {}",
+                words.invalid.len(),
+                describe(first)
+            ),
+        ),
+        None if walk.end.is_some() => {
+            report.push("utf-8", Outcome::Skip, "the structure is broken")
+        }
+        None => report.push(
+            "utf-8",
+            Outcome::Pass,
+            format!("{} word(s) are valid UTF-8", words.words),
+        ),
+    }
+    if !report.passed() {
+        return report.finish();
+    }
+
+    let bytes = match History::load_from_bytes(content).and_then(|history| to_bytes(&history)) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            report.push("round trip", Outcome::Fail, e.to_string());
+            return report;
+        }
+    };
+    if bytes == content {
+        report.push("round trip", Outcome::Pass, "byte-identical");
+    } else if version == FORMAT_VERSION_V3
+        && bytes.starts_with(&content[..header_len])
+        && decompress_pools(&bytes[header_len..]).is_ok_and(|decoded| decoded == *payload)
+    {
+        report.push(
+            "round trip",
+            Outcome::Pass,
+            "byte-identical after decompression, the compressed bytes differ",
+        );
+    } else {
+        let offset = content
+            .iter()
+            .zip(&bytes)
+            .position(|(lhs, rhs)| lhs != rhs)
+            .unwrap_or(content.len().min(bytes.len()));
+        report.push(
+            "round trip",
+            Outcome::Fail,
+            format!(
+                "re-serialized content ({} bytes) differs from the file ({} bytes) at offset 0x{:x}",
+                bytes.len(),
+                content.len(),
+                offset
+            ),
+        );
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{check, Outcome, Report};
+    use crate::{
+        data::History,
+        data_bytes::{FORMAT_VERSION_V3, MAX_DECOMPRESSED_SIZE},
+        test_util::history,
+        to_bytes,
+        wrapper::Wrapper,
+        Result,
+    };
+
+    fn sample() -> History {
+        history(vec![vec!["音乐 好听", "你好"], vec!["再见"], vec![]])
+    }
+
+    fn outcomes(report: &Report) -> Vec<(&str, Outcome)> {
+        report
+            .checks
+            .iter()
+            .map(|check| (check.name, check.outcome))
+            .collect()
+    }
+
+    #[test]
+    fn well_formed() -> Result<()> {
+        let report = check(&to_bytes(&sample())?);
+        assert!(report.passed());
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                ("wrapper", Outcome::Skip),
+                ("magic", Outcome::Pass),
+                ("version", Outcome::Pass),
+                ("decompression", Outcome::Skip),
+                ("structure", Outcome::Pass),
+                ("utf-8", Outcome::Pass),
+                ("round trip", Outcome::Pass),
+            ]
+        );
+        assert_eq!(
+            report.get("structure").unwrap().detail,
+            "3 pools of 2/1/0 sentence(s)"
+        );
+
+        let v3 = History {
+            format_version: FORMAT_VERSION_V3,
+            ..sample()
+        };
+        let report = check(&to_bytes(&v3)?);
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.get("decompression").unwrap().outcome, Outcome::Pass);
+
+        let bytes = to_bytes(&sample())?;
+        let report = check(&Wrapper::Gzip.wrap(&bytes)?);
+        assert!(report.passed(), "{}", report);
+        assert_eq!(
+            report.get("wrapper").unwrap().detail,
+            format!(
+                "gzip, offsets are relative to the {} unwrapped byte(s)",
+                bytes.len()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+
+        let report = check(&Wrapper::Xz.wrap(&bytes)?[..20]);
+        assert_eq!(report.get("wrapper").unwrap().outcome, Outcome::Fail);
+        assert_eq!(report.get("magic").unwrap().outcome, Outcome::Skip);
+
+        let mut bomb = bytes[..4].to_vec();
+        bomb.extend(FORMAT_VERSION_V3.to_be_bytes());
+        bomb.extend(zstd::encode_all(
+            &vec![0; MAX_DECOMPRESSED_SIZE + 1][..],
+            19,
+        )?);
+        let report = check(&bomb);
+        assert_eq!(
+            report.get("decompression").unwrap().detail,
+            format!(
+                "zstd: pools decompress to more than {} bytes",
+                MAX_DECOMPRESSED_SIZE
+            )
+        );
+        assert_eq!(report.get("structure").unwrap().outcome, Outcome::Skip);
+
+        let report = check(&bytes[..6]);
+        assert!(!report.passed());
+        assert_eq!(report.get("version").unwrap().outcome, Outcome::Fail);
+        assert_eq!(report.get("round trip").unwrap().outcome, Outcome::Skip);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = 1;
+        assert_eq!(
+            check(&bad_magic).get("magic").unwrap().detail,
+            "expected 0x000fc315, got 0x010fc315"
+        );
+
+        let report = check(&bytes[..bytes.len() - 7]);
+        assert_eq!(
+            report.get("structure").unwrap().detail,
+            "data ends inside word 1:0:0 (offset 0x3e)"
+        );
+        assert_eq!(report.get("utf-8").unwrap().outcome, Outcome::Skip);
+
+        let two_pools = to_bytes(&History::new(sample().pools[..2].to_vec()))?;
+        assert_eq!(
+            check(&two_pools).get("structure").unwrap().detail,
+            "data ends after 2 pool(s), libime expects 3 (offset 0x44)"
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            check(&trailing).get("structure").unwrap().detail,
+            "1 unexpected byte(s) after the last pool, from offset 0x48"
+        );
+
+        let mut invalid = bytes;
+        let at = invalid.len() - 8;
+        invalid[at] = 0xff;
+        let report = check(&invalid);
+        assert_eq!(
+            report.get("utf-8").unwrap().detail,
+            "1 word(s) are not valid UTF-8, the first one at offset 0x3e"
+        );
+        assert_eq!(report.get("round trip").unwrap().outcome, Outcome::Skip);
+        Ok(())
+    }
+}
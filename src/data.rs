@@ -1,16 +1,22 @@
-use std::{fmt::Display, fs::Permissions, os::unix::prelude::PermissionsExt, path::Path};
+use std::{
+    fmt::Display,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use serde::{
-    ser::{SerializeSeq, SerializeStruct},
+    ser::{self, SerializeSeq, SerializeStruct},
     Serialize, Serializer,
 };
 
 use crate::{
-    data_bytes::{FORMAT_VERSION_V2, MAGIC},
-    to_bytes, Error, Result,
+    data_bytes::{FORMAT_VERSION_V2, FORMAT_VERSION_V3, MAGIC},
+    persist, to_bytes,
+    wrapper::{self, Wrapper},
+    Error, Result,
 };
 
-#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, PartialOrd)]
 pub struct Word(
     /// Use `String` here because it is read from dumped `user.history` so it must be valid UTF-8.
     pub String,
@@ -41,7 +47,7 @@ impl Serialize for Word {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, PartialOrd)]
 pub struct Sentence(pub Vec<Word>);
 
 impl Sentence {
@@ -115,6 +121,29 @@ impl Serialize for Pool {
     }
 }
 
+/// Encodings a history can be loaded from, see [`History::detect_format`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// The [`libime`][libime]-compatible blob, see [`History::load_from_bytes`].
+    ///
+    /// [libime]: https://github.com/fcitx/libime
+    Binary,
+    /// A JSON document, see [`History::load_from_json`].
+    Json,
+    /// Newline-separated sentences, see [`History::load_from_text`].
+    Text,
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Format::Binary => "binary",
+            Format::Json => "json",
+            Format::Text => "text",
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct History {
     pub magic: u32,
@@ -131,29 +160,97 @@ impl History {
         }
     }
 
+    /// Atomically writes this history to `p` in the binary form, compressed with a
+    /// [`Wrapper`] if the file name ends in its extension, e.g. `user.history.gz`.  An existing
+    /// file at `p` keeps its permissions and ownership, a new file is created with mode `0600`.
     pub fn save<P>(&self, p: P) -> Result<()>
     where
         P: AsRef<Path>,
     {
-        std::fs::write(p.as_ref(), to_bytes(&self)?)?;
-        std::fs::set_permissions(p.as_ref(), Permissions::from_mode(0o600))?;
-        Ok(())
+        let bytes = to_bytes(&self)?;
+        match Wrapper::from_path(p.as_ref()) {
+            Some(wrapper) => persist::write_atomic(p.as_ref(), &wrapper.wrap(&bytes)?),
+            None => persist::write_atomic(p.as_ref(), &bytes),
+        }
+    }
+
+    /// Same as [`History::save`], but first copies an existing file at `p` to a timestamped
+    /// sibling `<file name>.<timestamp>.bak`.  Returns the path of the backup, if one was made.
+    pub fn save_with_backup<P>(&self, p: P) -> Result<Option<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        let backup = if p.as_ref().exists() {
+            Some(persist::backup(p.as_ref())?)
+        } else {
+            None
+        };
+        self.save(p)?;
+        Ok(backup)
+    }
+
+    /// Lists the backups of the file at `p` made by [`History::save_with_backup`].
+    pub fn backups<P>(p: P) -> Result<Vec<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        persist::backups(p.as_ref())
     }
 
+    /// Loads a history from the file at `p` in any of the supported [`Format`]s, see
+    /// [`History::parse`].
     pub fn load<P>(p: P) -> Result<Self>
     where
         P: AsRef<Path>,
     {
         let content = std::fs::read(&p)?;
-        match History::load_from_bytes(&content) {
-            Ok(hist) => Ok(hist),
-            _ => match History::load_from_text(&content) {
-                Ok(hist) => Ok(hist),
-                _ => Err(Error::DeserializeError(format!(
-                    "Could not load history from path '{}', tried binary and plain text",
-                    p.as_ref().display(),
-                ))),
-            },
+        History::parse(&content).map_err(|e| {
+            Error::DeserializeError(format!(
+                "Could not load history from path '{}': {}",
+                p.as_ref().display(),
+                e,
+            ))
+        })
+    }
+
+    /// Reads `reader` to its end and loads a history from the content, see [`History::parse`].
+    pub fn load_from_reader<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        History::parse(&content)
+    }
+
+    /// Guesses how `content` is encoded: blobs beginning with the file magic are binary, documents
+    /// beginning with a `{` are JSON, anything else is plain text.
+    pub fn detect_format(content: &[u8]) -> Format {
+        if content.starts_with(&MAGIC.to_be_bytes()) {
+            Format::Binary
+        } else if content
+            .iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|byte| *byte == b'{')
+        {
+            Format::Json
+        } else {
+            Format::Text
+        }
+    }
+
+    /// Loads a history from `content` in any of the supported [`Format`]s, first decompressing
+    /// it if it is wrapped in gzip, xz or zstd (see [`Wrapper`]).  A document that looks like
+    /// JSON but does not parse as such is loaded as plain text.
+    pub fn parse(content: &[u8]) -> Result<Self> {
+        let content = &wrapper::unwrap(content)?[..];
+        match History::detect_format(content) {
+            Format::Binary => History::load_from_bytes(content),
+            Format::Json => History::load_from_json(content).or_else(|e| {
+                log::debug!("not a JSON history ({}), trying plain text", e);
+                History::load_from_text(content)
+            }),
+            Format::Text => History::load_from_text(content),
         }
     }
 
@@ -205,9 +302,35 @@ impl Serialize for History {
         let mut ser = serializer.serialize_struct("HistoryData", 0)?;
         ser.serialize_field("magic", &self.magic)?;
         ser.serialize_field("format_version", &self.format_version)?;
-        for pool in &self.pools {
-            ser.serialize_field("pool", &pool)?;
+        if self.format_version == FORMAT_VERSION_V3 {
+            // Version 3 stores the pools ZSTD compressed after the header
+            let mut pools = Vec::new();
+            for pool in &self.pools {
+                pools.append(&mut to_bytes(pool).map_err(ser::Error::custom)?);
+            }
+            let compressed = zstd::encode_all(&pools[..], zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(ser::Error::custom)?;
+            ser.serialize_field("pools", &RawBytes(&compressed))?;
+        } else {
+            for pool in &self.pools {
+                ser.serialize_field("pool", &pool)?;
+            }
         }
         ser.end()
     }
 }
+
+/// Bytes that are written as-is, without a length prefix.
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
@@ -1,3 +1,5 @@
+use std::io::Read;
+
 use serde::{de::Visitor, Deserialize};
 
 use crate::{
@@ -10,6 +12,30 @@ use crate::{
 pub const MAGIC: u32 = 0x000FC315;
 pub const FORMAT_VERSION_V2: u32 = 0x02;
 pub const FORMAT_VERSION_V3: u32 = 0x03;
+/// Largest size, in bytes, compressed data is allowed to decompress to: the pools of a version 3
+/// history, or a history wrapped in general-purpose compression (see [`Wrapper`]).
+///
+/// [`Wrapper`]: crate::wrapper::Wrapper
+pub const MAX_DECOMPRESSED_SIZE: usize = 10 * 1024 * 1024;
+
+/// Decompresses the zstd compressed pools of a version 3 history, failing if they decompress to
+/// more than [`MAX_DECOMPRESSED_SIZE`] bytes.
+pub(crate) fn decompress_pools(compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut ret = Vec::new();
+    zstd::Decoder::new(compressed)?
+        .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+        .read_to_end(&mut ret)?;
+    if ret.len() > MAX_DECOMPRESSED_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "pools decompress to more than {} bytes",
+                MAX_DECOMPRESSED_SIZE
+            ),
+        ));
+    }
+    Ok(ret)
+}
 
 impl History {
     /// Load a history object from a [`libime`][libime]-compatible user history blob.  The format
@@ -180,13 +206,12 @@ impl<'de> Deserialize<'de> for HistoryFromBytes {
 
                         // Decompress using ZSTD
                         let decompressed_data =
-                            zstd::bulk::decompress(&compressed_data, 10 * 1024 * 1024) // 10MB limit
-                                .map_err(|e| {
-                                    serde::de::Error::custom(format!(
-                                        "ZSTD decompression failed: {}",
-                                        e
-                                    ))
-                                })?;
+                            decompress_pools(&compressed_data).map_err(|e| {
+                                serde::de::Error::custom(format!(
+                                    "ZSTD decompression failed: {}",
+                                    e
+                                ))
+                            })?;
 
                         // Parse exactly 3 pools from the decompressed data
                         let mut pools = Vec::new();
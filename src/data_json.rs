@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{History, Pool, Sentence, Word},
+    Result,
+};
+
+impl History {
+    /// Load a history object from a JSON document of the form
+    ///
+    /// ```json
+    /// {
+    ///   "magic": 1032981,
+    ///   "format_version": 2,
+    ///   "pools": [[["音乐", "好听"], ["你好"]], [], []]
+    /// }
+    /// ```
+    ///
+    /// where each pool is an array of sentences, newest first, and each sentence is an array of
+    /// words.  `magic` and `format_version` are optional.
+    pub fn load_from_json(content: &[u8]) -> Result<Self> {
+        let ret: HistoryJson = serde_json::from_slice(content)?;
+        Ok(History::from(ret))
+    }
+
+    /// Dumps this history as a pretty-printed JSON document, see [`History::load_from_json`].
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&HistoryJson::from(self))?)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryJson {
+    #[serde(default)]
+    pub magic: Option<u32>,
+    #[serde(default)]
+    pub format_version: Option<u32>,
+    pub pools: Vec<Vec<Vec<String>>>,
+}
+
+impl From<HistoryJson> for History {
+    fn from(hj: HistoryJson) -> Self {
+        let pools = hj
+            .pools
+            .into_iter()
+            .map(|pool| {
+                Pool(
+                    pool.into_iter()
+                        .map(|sentence| Sentence(sentence.into_iter().map(Word).collect()))
+                        .collect(),
+                )
+            })
+            .collect();
+        let mut ret = History::new(pools);
+        if let Some(magic) = hj.magic {
+            ret.magic = magic;
+        }
+        if let Some(format_version) = hj.format_version {
+            ret.format_version = format_version;
+        }
+        ret
+    }
+}
+
+impl From<&History> for HistoryJson {
+    fn from(history: &History) -> Self {
+        HistoryJson {
+            magic: Some(history.magic),
+            format_version: Some(history.format_version),
+            pools: history
+                .pools
+                .iter()
+                .map(|pool| {
+                    pool.0
+                        .iter()
+                        .map(|sentence| sentence.0.iter().map(|word| word.0.clone()).collect())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        data::{Format, History, Pool, Sentence, Word},
+        data_bytes::FORMAT_VERSION_V3,
+        to_bytes, Result,
+    };
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut history = History::new(vec![
+            Pool(vec![Sentence(vec![
+                Word("音乐".to_string()),
+                Word("好听".to_string()),
+            ])]),
+            Pool(vec![Sentence(vec![Word("🎵".to_string())])]),
+            Pool::default(),
+        ]);
+        history.format_version = FORMAT_VERSION_V3;
+        assert_eq!(
+            History::load_from_json(history.to_json()?.as_bytes())?,
+            history
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn header_is_optional() -> Result<()> {
+        let history = History::load_from_json(r#"{"pools": [[["你好", "世界"]]]}"#.as_bytes())?;
+        assert_eq!(
+            history,
+            History::new(vec![Pool(vec![Sentence(vec![
+                Word("你好".to_string()),
+                Word("世界".to_string()),
+            ])])])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detection() -> Result<()> {
+        let history = History::new(vec![Pool(vec![Sentence(vec![Word("{".to_string())])])]);
+        assert_eq!(History::detect_format(b" {}"), Format::Json);
+        assert_eq!(History::detect_format(b"{"), Format::Json);
+        assert_eq!(History::parse(b"{")?, history);
+        assert_eq!(History::parse(history.to_json()?.as_bytes())?, history);
+        assert_eq!(History::detect_format(&to_bytes(&history)?), Format::Binary);
+        Ok(())
+    }
+}
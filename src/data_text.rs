@@ -4,27 +4,133 @@ use crate::{
     data::{History, Pool, Sentence, Word},
     //de_text::{SequenceVisitor, StringVisitor},
     de::StringVisitor,
-    de_text::{SpaceSeparatedVisitor, TextDeserializer},
+    de_text::{from_text, SpaceSeparatedVisitor},
+    Error,
     Result,
 };
 
+/// Comment that marks the beginning of a pool in a text buffer, followed by the pool's index.
+pub const POOL_MARKER: &str = "# pool";
+
+const HEADER: &str = "\
+# One sentence per line, words separated by spaces, newest sentences first.
+# Lines starting with \"#\" are comments, except for the pool markers below, which start the
+# pools in order.  Sentences starting with \"#\" or \"\\\" are escaped with a leading \"\\\".
+";
+
+/// Returns the pool index of a pool marker, `comment` being the line without its leading `#`.
+/// A marker is `# pool <index>`, optionally followed by ` (<count> sentences)` as written by
+/// [`History::to_annotated_text`].
+fn pool_index(comment: &[u8]) -> Option<usize> {
+    let comment = std::str::from_utf8(comment).ok()?;
+    let rest = comment
+        .strip_prefix(POOL_MARKER.strip_prefix('#')?)?
+        .strip_prefix(' ')?;
+    let (index, count) = match rest.split_once(' ') {
+        Some((index, count)) => (index, Some(count)),
+        None => (rest, None),
+    };
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|byte| byte.is_ascii_digit());
+    let count_ok = count.is_none_or(|count| {
+        count
+            .strip_prefix('(')
+            .and_then(|count| count.strip_suffix(" sentences)"))
+            .is_some_and(is_number)
+    });
+    match is_number(index) && count_ok {
+        true => index.parse().ok(),
+        false => None,
+    }
+}
+
+/// Parses line `n` (counting from 0) of a text buffer as a sentence.
+fn parse_line(line: &[u8], n: usize) -> Result<Sentence> {
+    Ok(Sentence::from(
+        from_text::<SentenceFromText>(line)
+            .map_err(|e| Error::DeserializeError(format!("Line {}: {}", n + 1, e)))?,
+    ))
+}
+
 impl History {
     /// Load a history object from a newline-separated text buffer.  Each line should be a
-    /// space-separated collection of words.  Empty lines are ignored.
+    /// space-separated collection of words.  Empty lines are ignored.  Errors mention the number
+    /// of the offending line.
     pub fn load_from_text(content: &[u8]) -> Result<Self> {
-        let mut deserializer = TextDeserializer::new(content);
-        let mut sentences: Vec<SentenceFromText> = Vec::new();
-        while !deserializer.ended() {
-            let sft = SentenceFromText::deserialize(&mut deserializer)?;
-            sentences.push(sft);
+        let mut sentences = Vec::new();
+        for (n, line) in content.split(|byte| *byte == b'\n').enumerate() {
+            let sentence = parse_line(line, n)?;
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+        }
+        Ok(History::new(vec![Pool(sentences)]))
+    }
+
+    /// Same as [`History::load_from_text`], but for a text buffer written by
+    /// [`History::to_annotated_text`] and possibly edited since.
+    ///
+    /// Lines starting with `#` are comments and are ignored, except for pool markers (see
+    /// [`POOL_MARKER`]), which put the following sentences into the pool with the given index.
+    /// Pool markers must appear in order, starting with pool 0.  Without pool markers, all
+    /// sentences go into one pool.  One leading `\` is stripped from other lines, so that
+    /// sentences starting with `#` can be written as `\#...`.
+    pub fn load_from_annotated_text(content: &[u8]) -> Result<Self> {
+        let mut pools: Vec<Vec<Sentence>> = vec![Vec::new()];
+        let mut markers = 0;
+        for (n, line) in content.split(|byte| *byte == b'\n').enumerate() {
+            if let Some(comment) = line.strip_prefix(b"#") {
+                if let Some(i) = pool_index(comment) {
+                    if i != markers {
+                        return Err(Error::DeserializeError(format!(
+                            "Line {}: expected marker of pool {}, found pool {}",
+                            n + 1,
+                            markers,
+                            i,
+                        )));
+                    }
+                    markers += 1;
+                    if pools.len() <= i {
+                        pools.push(Vec::new());
+                    }
+                }
+                continue;
+            }
+            let sentence = parse_line(line.strip_prefix(b"\\").unwrap_or(line), n)?;
+            if !sentence.is_empty() {
+                pools
+                    .last_mut()
+                    .expect("there is at least one pool")
+                    .push(sentence);
+            }
+        }
+        Ok(History::new(pools.into_iter().map(Pool).collect()))
+    }
+
+    /// Dumps this history as a text buffer that explains its format in comments and marks the
+    /// beginning of each pool, see [`History::load_from_annotated_text`].  Loading the buffer
+    /// back gives a history with the same pools.
+    pub fn to_annotated_text(&self) -> String {
+        let mut ret = HEADER.to_string();
+        for (i, pool) in self.pools.iter().enumerate() {
+            ret.push_str(&format!(
+                "\n{} {} ({} sentences)\n",
+                POOL_MARKER,
+                i,
+                pool.0.len()
+            ));
+            for sentence in &pool.0 {
+                let line = sentence.to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                if line.starts_with('#') || line.starts_with('\\') {
+                    ret.push('\\');
+                }
+                ret.push_str(&line);
+                ret.push('\n');
+            }
         }
-        let sentences = sentences
-            .into_iter()
-            .map(Sentence::from)
-            .filter(|sentence| !sentence.is_empty())
-            .collect();
-        let pools = vec![Pool(sentences)];
-        Ok(History::new(pools))
+        ret
     }
 }
 
@@ -70,3 +176,61 @@ impl<'de> Deserialize<'de> for SentenceFromText {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{data::History, test_util::history, Error, Result};
+
+    #[test]
+    fn annotated_round_trip() -> Result<()> {
+        let history = history(vec![
+            vec!["音乐 好听", "#标签 你好"],
+            vec![],
+            vec!["\\n 换行", "再见"],
+        ]);
+        let text = history.to_annotated_text();
+        assert!(text.contains("\n# pool 0 (2 sentences)\n音乐 好听\n\\#标签 你好\n"));
+        assert!(text.contains("\n# pool 1 (0 sentences)\n\n# pool 2 (2 sentences)\n\\\\n"));
+        assert_eq!(History::load_from_annotated_text(text.as_bytes())?, history);
+        Ok(())
+    }
+
+    #[test]
+    fn comments_and_markers() -> Result<()> {
+        assert_eq!(
+            History::load_from_annotated_text("# a comment\n你好\n# pool 1\n".as_bytes()),
+            Err(Error::DeserializeError(
+                "Line 3: expected marker of pool 0, found pool 1".to_string()
+            ))
+        );
+        assert_eq!(
+            History::load_from_annotated_text(
+                "你好\n# pooling\n# pool 0\n音乐\n# pool 1 old\n# pool 1 (0 sentences)\n再见"
+                    .as_bytes()
+            )?,
+            history(vec![vec!["你好", "音乐"], vec!["再见"]])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn plain_text_has_no_comments() -> Result<()> {
+        assert_eq!(
+            History::load_from_text("#标签 你好\n\\n 换行\n# pool 1".as_bytes())?,
+            history(vec![vec!["#标签 你好", "\\n 换行", "# pool 1"]])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn error_line() {
+        assert_eq!(
+            History::load_from_text(b"\xe4\xbd\xa0 \xe5\xa5\xbd\n\n\xff\n"),
+            Err(Error::DeserializeError(
+                "Line 3: invalid utf-8 sequence of 1 bytes from index 0".to_string()
+            ))
+        );
+    }
+}
@@ -14,6 +14,11 @@ impl<'de> BytesDeserializer<'de> {
         Self { input }
     }
 
+    /// Number of bytes left to consume
+    pub fn remaining_len(&self) -> usize {
+        self.input.len()
+    }
+
     // Parsing helpers
     /// Consumes next `len` bytes from input and return it
     pub fn next_exact_bytes(&mut self, len: usize) -> Result<&[u8]> {
@@ -55,7 +60,7 @@ where
     Ok(t)
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut BytesDeserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut BytesDeserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
@@ -565,7 +570,7 @@ mod tests {
     #[test]
     fn history_v3_insufficient_data() {
         // Test error handling when there's not enough data after decompression
-        let pool_data = vec![0, 0, 0]; // Incomplete data
+        let pool_data = [0, 0, 0]; // Incomplete data
         let compressed_data = zstd::encode_all(&pool_data[..], 3).unwrap();
 
         let mut history_bytes = Vec::new();
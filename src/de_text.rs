@@ -15,12 +15,12 @@ impl<'de> TextDeserializer<'de> {
     }
 
     pub fn ended(&self) -> bool {
-        self.input.len() == 0
+        self.input.is_empty()
     }
 
     /// Returns the byte under cursor without consuming it
     pub fn peek_byte(&self) -> Result<u8> {
-        if self.input.len() > 0 {
+        if !self.input.is_empty() {
             Ok(self.input[0])
         } else {
             Err(Error::EofError)
@@ -54,7 +54,7 @@ impl<'de> TextDeserializer<'de> {
 
     /// Load next word, words are delimetered by space or new line character
     pub fn next_word(&mut self) -> Result<String> {
-        let ret = String::from_utf8(self.pop_until(&[b' ', b'\n'])?.into())?;
+        let ret = String::from_utf8(self.pop_until(b" \n")?.into())?;
         Ok(ret)
     }
 }
@@ -69,7 +69,7 @@ where
     Ok(t)
 }
 
-impl<'de, 'a> Deserializer<'de> for &'a mut TextDeserializer<'de> {
+impl<'de> Deserializer<'de> for &mut TextDeserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
@@ -315,7 +315,7 @@ impl<'a, 'de> SeqAccess<'de> for SpaceSeparated<'a, 'de> {
         if self.de.peek_byte().is_err() {
             return Ok(None);
         }
-        if !self.first && self.de.next_exact_bytes(1)? != &[b' '] {
+        if !self.first && self.de.next_exact_bytes(1)? != [b' '] {
             // return Err(Error::DeserializeError(
             //     "Expected space character".to_string(),
             // ));
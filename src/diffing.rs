@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::data::{History, Sentence};
+
+/// Sentences that differ between two histories, see [`History::diff`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistoryDiff {
+    /// Sentences that only appear in the old history, newest first.
+    pub removed: Vec<Sentence>,
+    /// Sentences that only appear in the new history, newest first.
+    pub added: Vec<Sentence>,
+}
+
+impl HistoryDiff {
+    /// Checks if both histories hold the same sentences
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+impl Display for HistoryDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(
+            &self
+                .removed
+                .iter()
+                .map(|s| format!("- {}", s))
+                .chain(self.added.iter().map(|s| format!("+ {}", s)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
+/// Returns sentences of `lhs` that are not matched by a sentence of `rhs`.  Repeated sentences are
+/// matched one to one, so a sentence that appears twice in `lhs` but once in `rhs` is returned
+/// once.
+fn unmatched(lhs: &[Sentence], rhs: &[Sentence]) -> Vec<Sentence> {
+    let mut counts: HashMap<&Sentence, usize> = HashMap::new();
+    for sentence in rhs {
+        *counts.entry(sentence).or_default() += 1;
+    }
+    lhs.iter()
+        .filter(|sentence| match counts.get_mut(sentence) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+impl History {
+    /// Compares the sentences of this history with those of `other`, regardless of which pool
+    /// they are in.
+    pub fn diff(&self, other: &History) -> HistoryDiff {
+        let old = self.get_sentences();
+        let new = other.get_sentences();
+        HistoryDiff {
+            removed: unmatched(&old, &new),
+            added: unmatched(&new, &old),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::HistoryDiff;
+    use crate::test_util::{history, sentence};
+
+    #[test]
+    fn identical_across_pools() {
+        let old = history(vec![vec!["音乐 好听"], vec!["你好"]]);
+        let new = history(vec![vec![], vec!["音乐 好听", "你好"]]);
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn repeated_sentences() {
+        let old = history(vec![vec!["你好", "音乐 好听", "你好"]]);
+        let new = history(vec![vec!["你好", "世界"]]);
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff,
+            HistoryDiff {
+                removed: vec![sentence("音乐 好听"), sentence("你好")],
+                added: vec![sentence("世界")],
+            }
+        );
+        assert_eq!(diff.to_string(), "- 音乐 好听\n- 你好\n+ 世界");
+    }
+}
@@ -57,6 +57,16 @@ impl From<std::string::FromUtf8Error> for Error {
         Self::Message(err.to_string())
     }
 }
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Message(err.to_string())
+    }
+}
+impl From<regex::Error> for Error {
+    fn from(err: regex::Error) -> Self {
+        Self::Message(err.to_string())
+    }
+}
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Self::IoError(err.to_string())
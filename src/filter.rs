@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use crate::{
+    data::{History, Sentence},
+    search::{Location, Pattern},
+};
+
+/// Criteria for removing sentences from a history, see [`History::remove_matching`].  A sentence
+/// matches the filter if it meets any of the criteria.  Lengths are counted in non-empty words.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Sentences matching any of these patterns
+    pub patterns: Vec<Pattern>,
+    /// Sentences containing any of these words
+    pub words: HashSet<String>,
+    /// Sentences with more words than this
+    pub longer_than: Option<usize>,
+    /// Sentences with fewer words than this
+    pub shorter_than: Option<usize>,
+    /// These very sentences
+    pub sentences: HashSet<Sentence>,
+}
+
+impl Filter {
+    /// Checks if the filter has no criteria, thus matches nothing.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+            && self.words.is_empty()
+            && self.longer_than.is_none()
+            && self.shorter_than.is_none()
+            && self.sentences.is_empty()
+    }
+
+    /// Checks if `sentence` meets any of the criteria.
+    pub fn matches(&self, sentence: &Sentence) -> bool {
+        let len = sentence.0.iter().filter(|word| !word.is_empty()).count();
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.is_match(sentence))
+            || sentence.0.iter().any(|word| self.words.contains(&word.0))
+            || self.longer_than.is_some_and(|n| len > n)
+            || self.shorter_than.is_some_and(|n| len < n)
+            || self.sentences.contains(sentence)
+    }
+}
+
+impl History {
+    /// Keeps only the sentences for which `f` returns `true`, and returns the removed ones with
+    /// the locations they had.  Sentences stay in the pools they were in, so pools may end up
+    /// holding fewer sentences than their capacities.
+    pub fn retain<F>(&mut self, mut f: F) -> Vec<(Location, Sentence)>
+    where
+        F: FnMut(&Sentence) -> bool,
+    {
+        let mut removed = Vec::new();
+        for (i, pool) in self.pools.iter_mut().enumerate() {
+            let sentences = std::mem::take(&mut pool.0);
+            for (j, sentence) in sentences.into_iter().enumerate() {
+                if f(&sentence) {
+                    pool.0.push(sentence);
+                } else {
+                    removed.push((Location { pool: i, index: j }, sentence));
+                }
+            }
+        }
+        removed
+    }
+
+    /// Removes the sentences matching `filter`, see [`History::retain`].
+    pub fn remove_matching(&mut self, filter: &Filter) -> Vec<(Location, Sentence)> {
+        self.retain(|sentence| !filter.matches(sentence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::Filter;
+    use crate::{
+        data::History,
+        search::{Pattern, Scope},
+        test_util::{history, sentence},
+        Result,
+    };
+
+    fn sample() -> History {
+        history(vec![
+            vec!["音乐 好听", "你好"],
+            vec!["我 喜欢 这 首 歌", "好 音乐"],
+            vec!["再见"],
+        ])
+    }
+
+    fn removed(filter: &Filter) -> Vec<String> {
+        sample()
+            .remove_matching(filter)
+            .into_iter()
+            .map(|(location, sentence)| format!("{} {}", location, sentence))
+            .collect()
+    }
+
+    #[test]
+    fn criteria() -> Result<()> {
+        assert!(Filter::default().is_empty());
+        assert!(removed(&Filter::default()).is_empty());
+        assert_eq!(
+            removed(&Filter {
+                patterns: vec![Pattern::regex("^好", Scope::Sentence)?],
+                ..Default::default()
+            }),
+            vec!["1:1 好 音乐"]
+        );
+        assert_eq!(
+            removed(&Filter {
+                words: ["音乐".to_string()].into(),
+                ..Default::default()
+            }),
+            vec!["0:0 音乐 好听", "1:1 好 音乐"]
+        );
+        assert_eq!(
+            removed(&Filter {
+                longer_than: Some(2),
+                shorter_than: Some(2),
+                ..Default::default()
+            }),
+            vec!["0:1 你好", "1:0 我 喜欢 这 首 歌", "2:0 再见"]
+        );
+        assert_eq!(
+            removed(&Filter {
+                sentences: [sentence("再见")].into(),
+                ..Default::default()
+            }),
+            vec!["2:0 再见"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_pools() {
+        let mut filtered = sample();
+        filtered.retain(|sentence| sentence.0.len() == 2);
+        assert_eq!(
+            filtered,
+            history(vec![vec!["音乐 好听"], vec!["好 音乐"], vec![]])
+        );
+    }
+}
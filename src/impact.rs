@@ -0,0 +1,181 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use crate::{bigram::HistoryBigram, data::History};
+
+/// How a pair of adjacent words changed between two histories.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransitionKind {
+    /// Occurs in both histories
+    Kept,
+    /// Only occurs in the new history
+    New,
+    /// Only occurs in the old history
+    Removed,
+}
+
+/// Scores of a pair of adjacent words in two histories, see [`HistoryBigram::score`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    pub prev: String,
+    pub cur: String,
+    pub before: f32,
+    pub after: f32,
+    pub kind: TransitionKind,
+}
+
+impl Transition {
+    /// Change of the score, positive if `cur` is more likely to be suggested after `prev`.
+    pub fn delta(&self) -> f32 {
+        self.after - self.before
+    }
+}
+
+/// What changing a history does to the suggestions of the input method, see
+/// [`History::impact`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Impact {
+    /// Words that only occur in the new history, sorted
+    pub new_words: Vec<String>,
+    /// Words that only occur in the old history, sorted
+    pub removed_words: Vec<String>,
+    /// Pairs of adjacent words whose scores changed, biggest change first
+    pub transitions: Vec<Transition>,
+}
+
+impl History {
+    /// Compares the scores libime's user language model (see [`HistoryBigram`]) gives pairs of
+    /// adjacent words in this history with those in `after`, e.g. the result of merging or
+    /// editing this history.  Pairs whose scores did not change are left out.
+    pub fn impact(&self, after: &History) -> Impact {
+        let (old, new) = (self.bigram(), after.bigram());
+
+        let old_words: BTreeSet<String> = old.words().into_iter().collect();
+        let new_words: BTreeSet<String> = new.words().into_iter().collect();
+
+        let old_bigrams: BTreeSet<(String, String)> = old.bigrams().into_iter().collect();
+        let new_bigrams: BTreeSet<(String, String)> = new.bigrams().into_iter().collect();
+        let mut transitions: Vec<Transition> = old_bigrams
+            .union(&new_bigrams)
+            .map(|(prev, cur)| Transition {
+                prev: prev.clone(),
+                cur: cur.clone(),
+                before: old.score(prev, cur),
+                after: new.score(prev, cur),
+                kind: kind(&old, &new, prev, cur),
+            })
+            .filter(|transition| {
+                transition.kind != TransitionKind::Kept || transition.delta() != 0.0
+            })
+            .collect();
+        transitions.sort_by(|lhs, rhs| {
+            rhs.delta()
+                .abs()
+                .total_cmp(&lhs.delta().abs())
+                .then_with(|| (&lhs.prev, &lhs.cur).cmp(&(&rhs.prev, &rhs.cur)))
+        });
+
+        Impact {
+            new_words: new_words.difference(&old_words).cloned().collect(),
+            removed_words: old_words.difference(&new_words).cloned().collect(),
+            transitions,
+        }
+    }
+}
+
+fn kind(old: &HistoryBigram, new: &HistoryBigram, prev: &str, cur: &str) -> TransitionKind {
+    match (
+        old.bigram_freq(prev, cur) > 0.0,
+        new.bigram_freq(prev, cur) > 0.0,
+    ) {
+        (false, _) => TransitionKind::New,
+        (_, false) => TransitionKind::Removed,
+        _ => TransitionKind::Kept,
+    }
+}
+
+impl Impact {
+    /// Checks if the suggestions are not affected at all.
+    pub fn is_empty(&self) -> bool {
+        self.new_words.is_empty() && self.removed_words.is_empty() && self.transitions.is_empty()
+    }
+}
+
+impl Display for TransitionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TransitionKind::Kept => "",
+            TransitionKind::New => "new",
+            TransitionKind::Removed => "removed",
+        })
+    }
+}
+
+impl Display for Impact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.new_words.is_empty() {
+            writeln!(f, "new words: {}", self.new_words.join(" "))?;
+        }
+        if !self.removed_words.is_empty() {
+            writeln!(f, "removed words: {}", self.removed_words.join(" "))?;
+        }
+        if !self.transitions.is_empty() {
+            if !self.new_words.is_empty() || !self.removed_words.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "{:>10} {:>10} {:>10} {:>8}  transition",
+                "before", "after", "change", ""
+            )?;
+            for t in &self.transitions {
+                writeln!(
+                    f,
+                    "{:>10.4} {:>10.4} {:>+10.4} {:>8}  {} {}",
+                    t.before,
+                    t.after,
+                    t.delta(),
+                    t.kind,
+                    t.prev,
+                    t.cur
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::TransitionKind;
+    use crate::test_util::history;
+
+    #[test]
+    fn impact() {
+        let before = history(vec![vec!["音乐 好听", "音乐 难听"]]);
+        let after = history(vec![vec!["音乐 好听", "音乐 好听", "你好"]]);
+        let impact = before.impact(&after);
+        assert_eq!(impact.new_words, vec!["你好".to_string()]);
+        assert_eq!(impact.removed_words, vec!["难听".to_string()]);
+        assert_eq!(
+            impact
+                .transitions
+                .iter()
+                .map(|t| (t.prev.as_str(), t.cur.as_str(), t.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("音乐", "难听", TransitionKind::Removed),
+                ("音乐", "好听", TransitionKind::Kept),
+            ]
+        );
+        assert!(impact.transitions[0].delta() < 0.0);
+        assert!(impact.transitions[1].delta() > 0.0);
+    }
+
+    #[test]
+    fn unchanged() {
+        let history = history(vec![vec!["音乐 好听"]]);
+        assert!(history.impact(&history.clone()).is_empty());
+    }
+}
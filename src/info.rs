@@ -0,0 +1,391 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek},
+    path::Path,
+};
+
+use crate::{
+    data_bytes::{FORMAT_VERSION_V2, FORMAT_VERSION_V3, MAGIC, MAX_DECOMPRESSED_SIZE},
+    walk::{walk, Field, Source},
+    wrapper::Wrapper,
+    Error, Result,
+};
+
+/// Summary of a binary history, read without loading its sentences, see [`Info::read`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Info {
+    /// The general-purpose compression the history was unwrapped from, if any
+    pub wrapper: Option<Wrapper>,
+    /// Size of the history, in bytes, after unwrapping
+    pub size: u64,
+    pub format_version: u32,
+    /// Number of sentences of each pool
+    pub pools: Vec<usize>,
+    /// Size of the pools, in bytes, after decompression for version 3
+    pub pools_size: u64,
+    /// Number of bytes after the last pool, after decompression for version 3
+    pub trailing: u64,
+}
+
+impl Info {
+    /// Size of the pools after decompression divided by their size in the file, for version 3
+    /// histories.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match self.format_version {
+            FORMAT_VERSION_V3 => Some(self.pools_size as f64 / (self.size - 8).max(1) as f64),
+            _ => None,
+        }
+    }
+
+    /// Number of sentences of all pools.
+    pub fn sentences(&self) -> usize {
+        self.pools.iter().sum()
+    }
+
+    /// Reads the header and the length prefixes of a binary history from `reader`, seeking over
+    /// the words of version 2 histories instead of reading them.  The pools of a version 3
+    /// history are decompressed as they are read, without being kept in memory, up to
+    /// [`MAX_DECOMPRESSED_SIZE`] bytes like [`History::load`](crate::data::History::load) does.  Exactly as many
+    /// pools are read as libime does, anything after them is reported as trailing data.
+    ///
+    /// A history wrapped in general-purpose compression (see [`Wrapper`]) is unwrapped into
+    /// memory first.
+    pub fn read<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let mut magic = Vec::new();
+        (&mut reader).take(8).read_to_end(&mut magic)?;
+        reader.rewind()?;
+        let Some(wrapper) = Wrapper::detect(&magic) else {
+            return Info::read_unwrapped(reader);
+        };
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        Ok(Info {
+            wrapper: Some(wrapper),
+            ..Info::read_unwrapped(Cursor::new(wrapper.unwrap(&content)?))?
+        })
+    }
+
+    fn read_unwrapped<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let size = reader.seek(std::io::SeekFrom::End(0))?;
+        reader.rewind()?;
+        let header = |field| {
+            move |e: std::io::Error| match e.kind() {
+                std::io::ErrorKind::UnexpectedEof => {
+                    Error::DeserializeError(format!("File ends inside the {}", field))
+                }
+                _ => Error::from(e),
+            }
+        };
+        let magic = read_u32(&mut reader).map_err(header("magic"))?;
+        if magic != MAGIC {
+            return Err(Error::DeserializeError(format!(
+                "Invalid history magic (expected 0x{:08x}, got 0x{:08x})",
+                MAGIC, magic,
+            )));
+        }
+        let format_version = read_u32(&mut reader).map_err(header("format version"))?;
+        let (walked, pools_size, offset) = match format_version {
+            FORMAT_VERSION_V2 => {
+                let mut source = Seeking {
+                    reader,
+                    offset: 8,
+                    size,
+                };
+                (walk(&mut source)?, source.offset - 8, "offset")
+            }
+            FORMAT_VERSION_V3 => {
+                let mut source = Streaming {
+                    reader: zstd::Decoder::new(reader)?.take(MAX_DECOMPRESSED_SIZE as u64 + 1),
+                    offset: 0,
+                };
+                let walked = walk(&mut source)?;
+                if source.reader.limit() == 0 {
+                    return Err(Error::DeserializeError(format!(
+                        "Pools decompress to more than {} bytes",
+                        MAX_DECOMPRESSED_SIZE
+                    )));
+                }
+                (walked, source.offset, "decompressed offset")
+            }
+            _ => {
+                return Err(Error::DeserializeError(format!(
+                    "Unsupported format version (expected 0x{:08x} or 0x{:08x}, got 0x{:08x})",
+                    FORMAT_VERSION_V2, FORMAT_VERSION_V3, format_version,
+                )))
+            }
+        };
+        if let Some(end) = walked.end {
+            return Err(Error::DeserializeError(format!(
+                "Invalid pools, {} ({} 0x{:x})",
+                end, offset, end.offset
+            )));
+        }
+        Ok(Info {
+            wrapper: None,
+            size,
+            format_version,
+            pools: walked.pools,
+            pools_size,
+            trailing: walked.trailing,
+        })
+    }
+
+    /// Reads the summary of the binary history at `p`, see [`Info::read`].
+    pub fn load<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Info::read(BufReader::new(File::open(&p)?)).map_err(|e| {
+            Error::DeserializeError(format!(
+                "Could not read history from path '{}': {}",
+                p.as_ref().display(),
+                e,
+            ))
+        })
+    }
+}
+
+impl Display for Info {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} bytes{}, version {}, {} sentence(s) in pools of {}",
+            self.size,
+            self.wrapper.map_or(String::new(), |wrapper| format!(
+                " unwrapped from {}",
+                wrapper
+            )),
+            self.format_version,
+            self.sentences(),
+            self.pools
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join("/")
+        )?;
+        if let Some(ratio) = self.compression_ratio() {
+            write!(
+                f,
+                ", compressed from {} bytes (ratio {:.2})",
+                self.pools_size, ratio
+            )?;
+        }
+        if self.trailing > 0 {
+            write!(f, ", {} byte(s) of trailing data", self.trailing)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pools read from a file, seeking over the words instead of reading them.
+struct Seeking<R> {
+    reader: R,
+    offset: u64,
+    /// Size of the file
+    size: u64,
+}
+
+impl<R: Read + Seek> Source for Seeking<R> {
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn u32(&mut self, _: Field) -> std::io::Result<Option<u32>> {
+        if self.offset + 4 > self.size {
+            return Ok(None);
+        }
+        self.offset += 4;
+        read_u32(&mut self.reader).map(Some)
+    }
+
+    fn bytes(&mut self, _: Field, len: u32) -> std::io::Result<bool> {
+        if self.offset + len as u64 > self.size {
+            return Ok(false);
+        }
+        self.offset += len as u64;
+        self.reader.seek_relative(len as i64)?;
+        Ok(true)
+    }
+
+    fn rest(&mut self) -> std::io::Result<u64> {
+        Ok(self.size - self.offset)
+    }
+}
+
+/// Pools read from a stream, reading and discarding the words.
+struct Streaming<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: Read> Source for Streaming<R> {
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn u32(&mut self, _: Field) -> std::io::Result<Option<u32>> {
+        match read_u32(&mut self.reader) {
+            Ok(n) => {
+                self.offset += 4;
+                Ok(Some(n))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn bytes(&mut self, _: Field, len: u32) -> std::io::Result<bool> {
+        let skipped = std::io::copy(
+            &mut (&mut self.reader).take(len as u64),
+            &mut std::io::sink(),
+        )?;
+        self.offset += skipped;
+        Ok(skipped == len as u64)
+    }
+
+    fn rest(&mut self) -> std::io::Result<u64> {
+        std::io::copy(&mut self.reader, &mut std::io::sink())
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> std::io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pretty_assertions::assert_eq;
+
+    use super::Info;
+    use crate::{
+        data::History,
+        data_bytes::{FORMAT_VERSION_V3, MAX_DECOMPRESSED_SIZE},
+        test_util::history,
+        to_bytes,
+        wrapper::Wrapper,
+        Error, Result,
+    };
+
+    fn sample() -> History {
+        history(vec![vec!["音乐 好听", "你好"], vec!["再见"], vec![]])
+    }
+
+    #[test]
+    fn v2() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+        let info = Info::read(Cursor::new(&bytes))?;
+        assert_eq!(
+            info,
+            Info {
+                wrapper: None,
+                size: bytes.len() as u64,
+                format_version: 2,
+                pools: vec![2, 1, 0],
+                pools_size: bytes.len() as u64 - 8,
+                trailing: 0,
+            }
+        );
+        assert_eq!(info.compression_ratio(), None);
+        assert_eq!(
+            info.to_string(),
+            "72 bytes, version 2, 3 sentence(s) in pools of 2/1/0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v3() -> Result<()> {
+        let v3 = History {
+            format_version: FORMAT_VERSION_V3,
+            ..sample()
+        };
+        let bytes = to_bytes(&v3)?;
+        let info = Info::read(Cursor::new(&bytes))?;
+        assert_eq!(info.pools, vec![2, 1, 0]);
+        assert_eq!(info.pools_size, 64);
+        assert_eq!(
+            info.compression_ratio(),
+            Some(64.0 / (bytes.len() - 8) as f64)
+        );
+        assert!(info
+            .to_string()
+            .contains(", compressed from 64 bytes (ratio "));
+        Ok(())
+    }
+
+    #[test]
+    fn too_large() -> Result<()> {
+        let mut bomb = to_bytes(&sample())?[..4].to_vec();
+        bomb.extend(FORMAT_VERSION_V3.to_be_bytes());
+        bomb.extend(zstd::encode_all(
+            &vec![0; MAX_DECOMPRESSED_SIZE + 1][..],
+            19,
+        )?);
+        assert_eq!(
+            Info::read(Cursor::new(&bomb)),
+            Err(Error::DeserializeError(format!(
+                "Pools decompress to more than {} bytes",
+                MAX_DECOMPRESSED_SIZE
+            )))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn wrapped() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+        let info = Info::read(Cursor::new(Wrapper::Xz.wrap(&bytes)?))?;
+        assert_eq!(
+            info,
+            Info {
+                wrapper: Some(Wrapper::Xz),
+                ..Info::read(Cursor::new(&bytes))?
+            }
+        );
+        assert_eq!(
+            info.to_string(),
+            "72 bytes unwrapped from xz, version 2, 3 sentence(s) in pools of 2/1/0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+        let error = |bytes: &[u8]| Info::read(Cursor::new(bytes)).unwrap_err().to_string();
+        assert_eq!(
+            error(&bytes[..6]),
+            "Deserialize Error: File ends inside the format version"
+        );
+        assert_eq!(
+            error(&bytes[..40]),
+            "Deserialize Error: Invalid pools, data ends inside word 0:1:1 length (offset 0x28)"
+        );
+        assert_eq!(
+            error(&bytes[..68]),
+            "Deserialize Error: Invalid pools, data ends after 2 pool(s), libime expects 3 \
+             (offset 0x44)"
+        );
+
+        let mut trailing = bytes;
+        trailing.extend([0, 0, 0, 0]);
+        let info = Info::read(Cursor::new(&trailing))?;
+        assert_eq!((&info.pools[..], info.trailing), (&[2, 1, 0][..], 4));
+        assert!(info
+            .to_string()
+            .ends_with("in pools of 2/1/0, 4 byte(s) of trailing data"));
+        assert!(Info::read(Cursor::new(b"text")).is_err());
+        Ok(())
+    }
+}
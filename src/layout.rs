@@ -0,0 +1,260 @@
+use crate::{
+    data::{History, Pool, Sentence},
+    merging::POOL_SIZE,
+    search::Location,
+    Error, Result,
+};
+
+impl History {
+    /// Moves the oldest sentences of each pool that holds more sentences than [`libime`][libime]
+    /// keeps in it (128, 8192 and 65536 sentences, from newest to oldest) to the newest end of
+    /// the next pool, creating that pool if needed.  Sentences that do not fit into the last
+    /// pool are dropped and returned, oldest last.  Pools after the third one are not limited.
+    ///
+    /// [libime]: https://github.com/fcitx/libime
+    pub fn rebalance(&mut self) -> Vec<Sentence> {
+        let mut dropped = Vec::new();
+        let mut i = 0;
+        while i < self.pools.len() {
+            let capacity = POOL_SIZE.get(i).copied().unwrap_or(usize::MAX);
+            if self.pools[i].0.len() > capacity {
+                let mut overflow = self.pools[i].0.split_off(capacity);
+                if i + 1 == self.pools.len() {
+                    if i + 1 < POOL_SIZE.len() {
+                        self.pools.push(Pool::default());
+                    } else {
+                        dropped.append(&mut overflow);
+                        i += 1;
+                        continue;
+                    }
+                }
+                overflow.append(&mut self.pools[i + 1].0);
+                self.pools[i + 1].0 = overflow;
+            }
+            i += 1;
+        }
+        dropped
+    }
+
+    /// Lays out all sentences, newest first, in the pools [`libime`][libime] keeps, filling each
+    /// pool before the next one.  This is what a history without pools, e.g. one loaded from
+    /// plain text, needs before libime can load it.  Sentences that do not fit are dropped and
+    /// returned, oldest last.
+    ///
+    /// [libime]: https://github.com/fcitx/libime
+    pub fn lay_out(&mut self) -> Vec<Sentence> {
+        let sentences = std::mem::take(&mut self.pools)
+            .into_iter()
+            .flat_map(|pool| pool.0)
+            .collect();
+        self.pools = vec![Pool::default(); POOL_SIZE.len()];
+        self.pools[0] = Pool(sentences);
+        self.rebalance()
+    }
+
+    /// Checks that this history has as many pools as [`libime`][libime] keeps, which it needs to
+    /// load the history.  A history loaded from plain text has one pool, see
+    /// [`History::lay_out`].
+    ///
+    /// [libime]: https://github.com/fcitx/libime
+    pub fn check_pools(&self) -> Result<()> {
+        match self.pools.len() == POOL_SIZE.len() {
+            true => Ok(()),
+            false => Err(Error::LogicError(format!(
+                "The history has {} pool(s), libime expects {}",
+                self.pools.len(),
+                POOL_SIZE.len()
+            ))),
+        }
+    }
+
+    /// Moves the sentences at `locations` to the newest end of pool 0, keeping their order, then
+    /// [rebalances](History::rebalance) the pools.  Returns the sentences that no longer fit.
+    /// Locations that do not exist are ignored.
+    pub fn move_to_newest(&mut self, locations: &[Location]) -> Vec<Sentence> {
+        let mut locations = locations.to_vec();
+        locations.sort();
+        locations.dedup();
+        let mut moved: Vec<Sentence> = Vec::with_capacity(locations.len());
+        // Remove from the back so that the remaining locations stay valid.
+        for location in locations.iter().rev() {
+            if self.get(*location).is_some() {
+                moved.push(self.pools[location.pool].0.remove(location.index));
+            }
+        }
+        moved.reverse();
+        self.prepend(moved)
+    }
+
+    /// Removes the sentence at `location` and all sentences older than it, and returns them with
+    /// the locations they had.  Pools that end up empty are kept.
+    pub fn drop_from(&mut self, location: Location) -> Vec<(Location, Sentence)> {
+        let mut removed = Vec::new();
+        for (i, pool) in self.pools.iter_mut().enumerate().skip(location.pool) {
+            let start = match i == location.pool {
+                true => location.index.min(pool.0.len()),
+                false => 0,
+            };
+            removed.extend(pool.0.drain(start..).enumerate().map(|(j, sentence)| {
+                (
+                    Location {
+                        pool: i,
+                        index: start + j,
+                    },
+                    sentence,
+                )
+            }));
+        }
+        removed
+    }
+
+    /// Keeps only the newest `n` sentences, see [`History::drop_from`].
+    pub fn keep_newest(&mut self, n: usize) -> Vec<(Location, Sentence)> {
+        let mut left = n;
+        for (i, pool) in self.pools.iter().enumerate() {
+            if left < pool.0.len() {
+                return self.drop_from(Location {
+                    pool: i,
+                    index: left,
+                });
+            }
+            left -= pool.0.len();
+        }
+        Vec::new()
+    }
+
+    /// Removes all sentences of the pool at index `pool`, and returns them with the locations
+    /// they had.  The other pools stay as they are, so older sentences keep their weights.
+    pub fn clear_pool(&mut self, pool: usize) -> Vec<(Location, Sentence)> {
+        match self.pools.get_mut(pool) {
+            Some(sentences) => std::mem::take(&mut sentences.0)
+                .into_iter()
+                .enumerate()
+                .map(|(index, sentence)| (Location { pool, index }, sentence))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Puts `newest` at the newest end of pool 0, then rebalances the pools.  Returns the
+    /// sentences that no longer fit.
+    pub(crate) fn prepend(&mut self, mut newest: Vec<Sentence>) -> Vec<Sentence> {
+        if self.pools.is_empty() {
+            self.pools.push(Pool::default());
+        }
+        newest.append(&mut self.pools[0].0);
+        self.pools[0].0 = newest;
+        self.rebalance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use std::io::Cursor;
+
+    use crate::{
+        check::check,
+        data::{History, Pool, Sentence},
+        info::Info,
+        search::Location,
+        test_util::{history, sentence},
+        to_bytes, Result,
+    };
+
+    fn numbered(range: std::ops::Range<usize>) -> Vec<Sentence> {
+        range.map(|i| sentence(&i.to_string())).collect()
+    }
+
+    #[test]
+    fn rebalance() {
+        let mut history = History::new(vec![Pool(numbered(0..130))]);
+        assert!(history.rebalance().is_empty());
+        assert_eq!(
+            history,
+            History::new(vec![Pool(numbered(0..128)), Pool(numbered(128..130))])
+        );
+
+        let mut history = History::new(vec![
+            Pool(numbered(0..129)),
+            Pool(numbered(129..129 + 8192)),
+            Pool(numbered(8321..8321 + 65536)),
+        ]);
+        assert_eq!(history.rebalance(), vec![sentence("73856")]);
+        assert_eq!(
+            history
+                .pools
+                .iter()
+                .map(|pool| pool.0.len())
+                .collect::<Vec<_>>(),
+            vec![128, 8192, 65536]
+        );
+        assert_eq!(history.pools[1].0[0], sentence("128"));
+        assert_eq!(history.pools[2].0[0], sentence("8320"));
+    }
+
+    #[test]
+    fn lay_out() -> Result<()> {
+        let mut compiled = History::load_from_text("音乐 好听\n你好".as_bytes())?;
+        assert!(compiled.check_pools().is_err());
+        assert!(compiled.lay_out().is_empty());
+        assert_eq!(compiled.check_pools(), Ok(()));
+        assert_eq!(
+            compiled,
+            history(vec![vec!["音乐 好听", "你好"], vec![], vec![]])
+        );
+        let bytes = to_bytes(&compiled)?;
+        assert!(check(&bytes).passed());
+        assert_eq!(Info::read(Cursor::new(&bytes))?.pools, vec![2, 0, 0]);
+
+        let mut long = History::new(vec![Pool(numbered(0..100)), Pool(numbered(100..73900))]);
+        assert_eq!(long.lay_out(), numbered(73856..73900));
+        assert_eq!(long.pools[1].0[0], sentence("128"));
+        assert_eq!(long.pools[2].0.len(), 65536);
+        Ok(())
+    }
+
+    #[test]
+    fn move_to_newest() {
+        let mut moved = history(vec![vec!["a", "b"], vec!["c", "d"]]);
+        let dropped = moved.move_to_newest(&[
+            Location { pool: 1, index: 1 },
+            Location { pool: 0, index: 1 },
+            Location { pool: 1, index: 1 },
+            Location { pool: 5, index: 0 },
+        ]);
+        assert!(dropped.is_empty());
+        assert_eq!(moved, history(vec![vec!["b", "d", "a"], vec!["c"]]));
+    }
+
+    fn formatted(removed: Vec<(Location, Sentence)>) -> Vec<String> {
+        removed
+            .into_iter()
+            .map(|(location, sentence)| format!("{} {}", location, sentence))
+            .collect()
+    }
+
+    #[test]
+    fn trim() {
+        let original = history(vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+
+        let mut trimmed = original.clone();
+        assert_eq!(formatted(trimmed.keep_newest(3)), vec!["1:1 d", "2:0 e"]);
+        assert_eq!(trimmed, history(vec![vec!["a", "b"], vec!["c"], vec![]]));
+        assert!(trimmed.keep_newest(10).is_empty());
+        assert_eq!(formatted(trimmed.keep_newest(2)), vec!["1:0 c"]);
+
+        let mut trimmed = original.clone();
+        assert_eq!(
+            formatted(trimmed.drop_from(Location { pool: 0, index: 5 })),
+            vec!["1:0 c", "1:1 d", "2:0 e"]
+        );
+        assert_eq!(trimmed.get_sentences(), vec![sentence("a"), sentence("b")]);
+
+        let mut cleared = original;
+        assert_eq!(formatted(cleared.clear_pool(1)), vec!["1:0 c", "1:1 d"]);
+        assert_eq!(cleared, history(vec![vec!["a", "b"], vec![], vec!["e"]]));
+        assert!(cleared.clear_pool(3).is_empty());
+    }
+}
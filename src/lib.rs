@@ -1,21 +1,65 @@
+pub mod arpa;
+pub mod bigram;
+pub mod blocklist;
+pub mod check;
 pub mod data;
 pub mod data_bytes;
+pub mod data_json;
 pub mod data_text;
+pub mod filter;
+pub mod impact;
+pub mod info;
+pub mod locate;
+pub mod predict;
+pub mod promote;
+pub mod replace;
+pub mod scan;
+pub mod search;
+pub mod seed;
+pub mod snapshot;
+pub mod stats;
+pub mod structure;
+pub mod wrapper;
 
 mod de;
 mod de_bytes;
 mod de_text;
+mod diffing;
 mod error;
+mod layout;
 mod merging;
+mod persist;
 mod ser;
 mod utils;
+mod walk;
 
 pub use de_bytes::{from_bytes, BytesDeserializer};
 pub use de_text::{from_text, TextDeserializer};
+pub use diffing::HistoryDiff;
 pub use error::{Error, Result};
-pub use merging::merge;
+pub use merging::{merge, merge_with};
 pub use ser::{to_bytes, Serializer};
 
+#[cfg(test)]
+mod test_util {
+    use crate::data::{History, Pool, Sentence, Word};
+
+    /// Makes a sentence from its words, separated by spaces.
+    pub(crate) fn sentence(content: &str) -> Sentence {
+        Sentence(content.split(' ').map(String::from).map(Word).collect())
+    }
+
+    /// Makes a history from its pools, each given as its sentences, newest first.
+    pub(crate) fn history(pools: Vec<Vec<&str>>) -> History {
+        History::new(
+            pools
+                .into_iter()
+                .map(|pool| Pool(pool.into_iter().map(sentence).collect()))
+                .collect(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod serde_tests {
     use pretty_assertions::assert_eq;
@@ -87,6 +131,21 @@ mod serde_tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn history_v3() -> Result<()> {
+        let sentence = Sentence(vec![Word("音乐".to_string()), Word("好听".to_string())]);
+        let history = History {
+            magic: crate::data_bytes::MAGIC,
+            format_version: crate::data_bytes::FORMAT_VERSION_V3,
+            pools: vec![Pool(vec![sentence]), Pool::default(), Pool::default()],
+        };
+        assert_eq!(
+            history,
+            History::from(from_bytes::<HistoryFromBytes>(&to_bytes(&history)?)?)
+        );
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,111 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{utils::xdg_dir, Result};
+
+/// The input method whose history is used when none is specified.
+pub const DEFAULT_IME: &str = "pinyin";
+
+/// Returns [`fcitx5`][fcitx5]'s data directory, `$XDG_DATA_HOME/fcitx5`, where `$XDG_DATA_HOME`
+/// defaults to `~/.local/share`.
+///
+/// [fcitx5]: https://github.com/fcitx/fcitx5
+pub fn fcitx5_data_dir() -> Result<PathBuf> {
+    Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join("fcitx5"))
+}
+
+/// Returns where the history of the input method `ime` lives inside the fcitx5 data directory
+/// `data_dir`.  The pinyin input method keeps its history at `pinyin/user.history`, table-based
+/// input methods keep theirs at `table/<name>.history`.
+///
+/// REF: <https://github.com/fcitx/fcitx5-chinese-addons>
+pub fn history_path_in<P>(data_dir: P, ime: &str) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    match ime {
+        "pinyin" | "shuangpin" => data_dir.as_ref().join("pinyin").join("user.history"),
+        table => data_dir
+            .as_ref()
+            .join("table")
+            .join(format!("{}.history", table)),
+    }
+}
+
+/// Returns where the history of the input method `ime` lives, see [`history_path_in`].
+pub fn history_path(ime: &str) -> Result<PathBuf> {
+    Ok(history_path_in(fcitx5_data_dir()?, ime))
+}
+
+/// Recursively collects files whose names end with `.history` under `dir`, sorted by path.
+/// Returns an empty list if `dir` does not exist.
+pub fn find_histories_in<P>(dir: P) -> Result<Vec<PathBuf>>
+where
+    P: AsRef<Path>,
+{
+    let mut ret = Vec::new();
+    if !dir.as_ref().is_dir() {
+        return Ok(ret);
+    }
+    for entry in fs::read_dir(dir.as_ref())? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            ret.append(&mut find_histories_in(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "history") {
+            ret.push(path);
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Collects all history files inside the fcitx5 data directory, see [`find_histories_in`].
+pub fn find_histories() -> Result<Vec<PathBuf>> {
+    find_histories_in(fcitx5_data_dir()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{find_histories_in, history_path_in};
+    use crate::Result;
+
+    #[test]
+    fn history_paths() {
+        let dir = Path::new("/data/fcitx5");
+        assert_eq!(
+            history_path_in(dir, "pinyin"),
+            Path::new("/data/fcitx5/pinyin/user.history")
+        );
+        assert_eq!(
+            history_path_in(dir, "wbx"),
+            Path::new("/data/fcitx5/table/wbx.history")
+        );
+    }
+
+    #[test]
+    fn find() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir_all(dir.path().join("pinyin"))?;
+        fs::create_dir_all(dir.path().join("table"))?;
+        fs::write(dir.path().join("pinyin/user.history"), b"")?;
+        fs::write(dir.path().join("pinyin/user.dict"), b"")?;
+        fs::write(dir.path().join("table/wbx.history"), b"")?;
+
+        assert_eq!(
+            find_histories_in(dir.path())?,
+            vec![
+                dir.path().join("pinyin/user.history"),
+                dir.path().join("table/wbx.history"),
+            ]
+        );
+        assert!(find_histories_in(dir.path().join("missing"))?.is_empty());
+        Ok(())
+    }
+}
@@ -1,16 +1,45 @@
-use std::path::PathBuf;
+use std::{
+    cell::Cell,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
-use libime_history_merge::{data::History, merge, Error, Result};
+use libime_history_merge::{
+    blocklist::Blocklist,
+    check,
+    data::{Format, History},
+    data_text::POOL_MARKER,
+    filter::Filter,
+    info::Info,
+    locate, merge, promote,
+    replace::Replacement,
+    scan::{Detector, Redaction},
+    search::{Location, Pattern, Scope},
+    seed::{self, Lexicon},
+    snapshot::SnapshotStore,
+    stats::Stats,
+    structure, to_bytes, Error, Result,
+};
 use structopt::StructOpt;
 
+#[cfg(feature = "tui")]
+mod tui;
+
 /// Inspect/Merge one or more `user.history` files.
+///
+/// Run without a subcommand, it behaves like the `inspect` subcommand if -o|--output is not
+/// specified and like the `merge` subcommand otherwise.
 #[derive(Debug, StructOpt)]
 #[structopt(
     global_settings(&[structopt::clap::AppSettings::ColoredHelp]),
 )]
 pub struct Opt {
-    /// Path to a history file, in binary or in plain-text.
-    pub user_history_path: PathBuf,
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to a history file, in binary, JSON or plain-text.  Pass "-" to read from stdin.
+    /// Defaults to the history of the input method specified by --ime.
+    pub user_history_path: Option<PathBuf>,
 
     /// More history files.
     pub more_paths: Vec<PathBuf>,
@@ -20,11 +49,21 @@ pub struct Opt {
     #[structopt(short, long, use_delimiter = true)]
     pub weights: Vec<u8>,
 
-    /// If present, write merged history data to specified path;  If not present, inspect the
-    /// merged history data in plain text.
+    /// If present, write merged history data to specified path ("-" for stdout);  If not
+    /// present, inspect the merged history data in plain text.
     #[structopt(short, long)]
     pub output: Option<PathBuf>,
 
+    /// If present, allow overwriting an existing output path.  The overwritten file is kept as a
+    /// timestamped backup next to it.
+    #[structopt(short, long)]
+    pub force: bool,
+
+    /// If present, write the result back to the first history file instead of to
+    /// -o|--output.  The original file is kept as a timestamped backup next to it.
+    #[structopt(short, long, conflicts_with = "output")]
+    pub in_place: bool,
+
     /// If present, let the user edit the output history.
     #[structopt(short, long)]
     pub edit: bool,
@@ -33,6 +72,571 @@ pub struct Opt {
     /// value).
     #[structopt(short, long)]
     pub no_pager: bool,
+
+    /// The input method whose history is used when no history file is given: "pinyin" for
+    /// $XDG_DATA_HOME/fcitx5/pinyin/user.history, any other name for the table-based input
+    /// method's $XDG_DATA_HOME/fcitx5/table/<name>.history.
+    #[structopt(long, global = true, default_value = locate::DEFAULT_IME)]
+    pub ime: String,
+
+    /// Drop sentences blocked by this blocklist file from every loaded or merged history,
+    /// instead of by $XDG_CONFIG_HOME/libime-history-merge/blocklist (if it exists).  Each line
+    /// of a blocklist is a word, a phrase of space-separated words, or a /regular expression/
+    /// matched against sentences.  Lines starting with "#" are ignored.
+    #[structopt(long, global = true)]
+    pub blocklist: Option<PathBuf>,
+
+    /// If present, do not apply any blocklist.
+    #[structopt(long, global = true, conflicts_with = "blocklist")]
+    pub no_blocklist: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Show one or more history files in plain text.
+    Inspect {
+        /// History files, in binary, JSON or plain-text.  Pass "-" to read from stdin.  Defaults
+        /// to the history of the input method specified by --ime.
+        paths: Vec<PathBuf>,
+
+        /// If present, show the histories as JSON documents instead of plain text.
+        #[structopt(short, long)]
+        json: bool,
+
+        #[structopt(flatten)]
+        pager: PagerOpt,
+    },
+    /// Merge history files into one.
+    Merge {
+        /// History files, in binary, JSON or plain-text.  Pass "-" to read from stdin.
+        #[structopt(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// A list of integer values (e.g. "-w3,5" or "-w 4 1"), represents relative weights
+        /// assigned to each of the input history data while merging, sum of weights are
+        /// normalized to 1.
+        #[structopt(short, long, use_delimiter = true)]
+        weights: Vec<u8>,
+
+        /// If present, let the user edit the merged history before it is saved.
+        #[structopt(short, long)]
+        edit: bool,
+
+        #[structopt(flatten)]
+        output: OutputOpt,
+    },
+    /// Compile a plain-text history into the binary form, laying out its sentences in libime's
+    /// pools.
+    Compile {
+        /// A history file in plain-text, one space-separated sentence per line.  Pass "-" to read
+        /// from stdin.
+        path: PathBuf,
+
+        #[structopt(flatten)]
+        output: OutputOpt,
+    },
+    /// Generate a history from plain-text documents, e.g. to give a new machine a head start.
+    /// The documents are split into sentences at line breaks and punctuation marks, and each
+    /// sentence is split into the longest words found in a word list.  The result can be merged
+    /// into an existing history with the `merge` subcommand.
+    Seed {
+        /// Plain-text documents, in UTF-8.  Pass "-" to read from stdin.  Sentences towards the
+        /// end count as the newest.
+        #[structopt(required = true)]
+        corpus: Vec<PathBuf>,
+
+        /// A word list, one word per line.  Only the first field of a line counts, empty lines
+        /// and lines starting with "#" are ignored.
+        #[structopt(short, long)]
+        lexicon: PathBuf,
+
+        /// Write the generated history to specified path, "-" writes to stdout if it is not a
+        /// terminal.
+        #[structopt(short, long)]
+        output: PathBuf,
+
+        /// If present, allow overwriting an existing output path.  The overwritten file is kept
+        /// as a timestamped backup next to it.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Show sentences removed (-) from and added (+) to a history file.
+    Diff {
+        /// The old history file.
+        old: PathBuf,
+
+        /// The new history file.
+        new: PathBuf,
+
+        #[structopt(flatten)]
+        pager: PagerOpt,
+    },
+    /// Show how the scores libime gives pairs of adjacent words change from one history file to
+    /// another, e.g. before and after a merge or an edit.  Words and pairs of words that are new
+    /// or removed are flagged.
+    Impact {
+        /// The old history file.
+        old: PathBuf,
+
+        /// The new history file.
+        new: PathBuf,
+
+        /// Number of pairs of words with the biggest changes to show.
+        #[structopt(short, long, default_value = "20")]
+        top: usize,
+
+        #[structopt(flatten)]
+        pager: PagerOpt,
+    },
+    /// Find sentences containing a string, or matching a regular expression, in history files.
+    /// Matches are printed as "<file>:<pool>:<index>: <sentence>", where index 0 is the newest
+    /// sentence of the pool, neighbouring sentences as "<file>-<pool>-<index>- <sentence>".
+    Search {
+        /// The string to look for.
+        pattern: String,
+
+        /// History files, in binary, JSON or plain-text.  Pass "-" to read from stdin.  Defaults
+        /// to the history of the input method specified by --ime.
+        paths: Vec<PathBuf>,
+
+        /// If present, treat the pattern as a regular expression.
+        #[structopt(short = "E", long)]
+        regex: bool,
+
+        /// If present, match the pattern against whole sentences, rendered with their words
+        /// separated by spaces, instead of against each word.
+        #[structopt(short, long)]
+        sentence: bool,
+
+        /// Number of neighbouring sentences of the same pool to show around each match.
+        #[structopt(short = "C", long, default_value = "0")]
+        context: usize,
+    },
+    /// Remove sentences from a history file.  A sentence is removed if it meets any of the given
+    /// criteria.  Sentences stay in the pools they were in.
+    Filter {
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// Remove sentences matching this regular expression, matched against whole sentences
+        /// with their words separated by spaces.  Can be given multiple times.
+        #[structopt(short = "E", long = "regex", number_of_values = 1)]
+        regexes: Vec<String>,
+
+        /// Remove sentences containing this word.  Can be given multiple times.
+        #[structopt(short, long = "word", number_of_values = 1)]
+        words: Vec<String>,
+
+        /// Remove sentences with more words than this.
+        #[structopt(long)]
+        longer_than: Option<usize>,
+
+        /// Remove sentences with fewer words than this.
+        #[structopt(long)]
+        shorter_than: Option<usize>,
+
+        /// Remove sentences listed in this plain-text file, one space-separated sentence per
+        /// line.
+        #[structopt(short, long)]
+        listed_in: Option<PathBuf>,
+
+        /// If present, only show the sentences that would be removed.
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// If present, write the filtered history to specified path;  If not present, write it
+        /// back to the filtered file, keeping the original as a timestamped backup next to it.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Remove old sentences from a history file, e.g. to reset the habits a machine has learned
+    /// without deleting the file.  The remaining sentences stay in the pools they were in.
+    Trim {
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// Keep only the newest <keep> sentences.
+        #[structopt(short, long)]
+        keep: Option<usize>,
+
+        /// Remove the sentence at this location, written as "<pool>:<index>" (e.g. "1:0", as
+        /// shown by `search`), and all sentences older than it.
+        #[structopt(long)]
+        drop_from: Option<Location>,
+
+        /// Remove all sentences of the pool at this index, 0 being the pool of the newest
+        /// sentences.  Can be given multiple times.
+        #[structopt(short, long = "clear-pool", number_of_values = 1)]
+        clear_pools: Vec<usize>,
+
+        /// If present, show the sentences that would be removed instead of removing them.
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// If present, write the result to specified path;  If not present, write it back to the
+        /// input file, keeping the original as a timestamped backup next to it.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Replace words in a history file with other words, e.g. to fix a typo that keeps being
+    /// suggested.
+    Replace {
+        /// The word to replace, or a regular expression matched inside each word with -E|--regex.
+        from: String,
+
+        /// What to replace the word with.  Separate words with spaces to replace it with a
+        /// sequence of words, pass an empty string to remove it.  With -E|--regex, "$1", "$name",
+        /// etc. refer to the matched groups.
+        to: String,
+
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// If present, treat the word to replace as a regular expression.
+        #[structopt(short = "E", long)]
+        regex: bool,
+
+        /// If present, show the replaced (-) and resulting (+) sentences.
+        #[structopt(short, long)]
+        diff: bool,
+
+        /// If present, do not write the result.
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// If present, write the result to specified path;  If not present, write it back to the
+        /// input file, keeping the original as a timestamped backup next to it.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Make the input method rank phrases, e.g. team jargon or project names, higher by moving
+    /// them to the newest position of a history file, adding those it does not hold.  The
+    /// oldest sentences are dropped if the pools overflow.
+    Promote {
+        /// A file listing the phrases, one per line with words separated by spaces, e.g.
+        /// "项目 代号".  Empty lines and lines starting with "#" are ignored.  Pass "-" to read
+        /// from stdin.
+        phrases: PathBuf,
+
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// If present, insert this many copies of each phrase at the newest position instead,
+        /// which raises their weights further.
+        #[structopt(short, long)]
+        boost: Option<usize>,
+
+        /// If present, do not write the result.
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// If present, write the result to specified path;  If not present, write it back to the
+        /// input file, keeping the original as a timestamped backup next to it.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Look for sensitive data, e.g. phone numbers or email addresses, in history files.
+    /// Findings are printed as "<file>:<pool>:<index>: [<detector>] <text>".  Exits with a
+    /// non-zero status if anything is found.
+    Scan {
+        /// History files, in binary, JSON or plain-text.  Pass "-" to read from stdin.  Defaults
+        /// to the history of the input method specified by --ime.
+        paths: Vec<PathBuf>,
+
+        #[structopt(flatten)]
+        detectors: DetectorOpt,
+    },
+    /// Remove or mask sentences holding sensitive data in a history file, see the `scan`
+    /// subcommand.  The result is written without keeping a backup or a snapshot of the original.
+    /// Backups and snapshots of the history file made earlier still hold the sensitive data, they
+    /// are listed with a warning, or deleted with --purge.
+    Redact {
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        #[structopt(flatten)]
+        detectors: DetectorOpt,
+
+        /// If present, remove sentences holding sensitive data instead of masking the sensitive
+        /// characters with "*".
+        #[structopt(short, long)]
+        remove: bool,
+
+        /// If present, only show the sensitive data that would be redacted.
+        #[structopt(long)]
+        dry_run: bool,
+
+        /// If present, write the redacted history to specified path;  If not present, write it
+        /// back to the input file.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+
+        /// If present, delete the timestamped backups next to the input file and its snapshots.
+        #[structopt(long)]
+        purge: bool,
+    },
+    /// Show what history files hold: sentence and word counts per pool, vocabulary size, average
+    /// sentence length, and the most frequent words and pairs of adjacent words.
+    Stats {
+        /// History files, in binary, JSON or plain-text.  Pass "-" to read from stdin.  Defaults
+        /// to the history of the input method specified by --ime.
+        paths: Vec<PathBuf>,
+
+        /// Number of most frequent words and pairs of adjacent words to show.
+        #[structopt(short, long, default_value = "10")]
+        top: usize,
+
+        /// If present, show the statistics as a JSON array with one object per history file.
+        #[structopt(short, long)]
+        json: bool,
+    },
+    /// List words that follow a word, or a sequence of words, in a history file, most likely
+    /// first, with their counts weighted by pool and the scores libime gives them.
+    Predict {
+        /// The word, or sequence of words, to list the following words of.
+        #[structopt(required = true)]
+        words: Vec<String>,
+
+        /// A history file, in binary, JSON or plain-text.  Pass "-" to read from stdin.  Defaults
+        /// to the history of the input method specified by --ime.
+        #[structopt(short, long)]
+        path: Option<PathBuf>,
+
+        /// Number of following words to show.
+        #[structopt(short, long, default_value = "10")]
+        top: usize,
+
+        /// If present, also show the sentences in which each word follows.
+        #[structopt(short, long)]
+        sentences: bool,
+    },
+    /// Export a history file as an ARPA unigram/bigram language model, with counts taken from
+    /// its sentences, absolute discounting and backoff.
+    Arpa {
+        /// A history file, in binary, JSON or plain-text.  Pass "-" to read from stdin.  Defaults
+        /// to the history of the input method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// If present, write the model to specified path;  If not present, write it to stdout.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Edit a history file in an editor (possibly specified by the environment variable $VISUAL
+    /// or $EDITOR).
+    Edit {
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// If present, write the edited history to specified path;  If not present, write it back
+        /// to the edited file, keeping the original as a timestamped backup next to it.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Browse a history file in the terminal, with a tab per pool, incremental search, deleting
+    /// and moving sentences to the newest position, and undo.  Press "s" to save and "q" to quit.
+    Tui {
+        /// A history file, in binary, JSON or plain-text.  Defaults to the history of the input
+        /// method specified by --ime.
+        path: Option<PathBuf>,
+
+        /// If present, save the history to specified path;  If not present, save it back to the
+        /// browsed file, keeping the original as a timestamped backup next to it.  Only the first
+        /// save of a session keeps a backup and a snapshot, later saves overwrite the file.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+
+        /// If present, allow overwriting an existing output path.
+        #[structopt(short, long)]
+        force: bool,
+    },
+    /// Check that binary history files are well-formed and survive a round trip through this
+    /// tool, without needing libime's own tools.  The magic, format version, decompression (for
+    /// version 3), pool structure, UTF-8 validity of words and a byte-identical round trip are
+    /// checked and reported one by one.  Exits with a non-zero status if any check fails.
+    Check {
+        /// History files in binary, possibly wrapped in gzip, xz or zstd.  Pass "-" to read from
+        /// stdin.  Defaults to the history of the input method specified by --ime.
+        paths: Vec<PathBuf>,
+    },
+    /// Show each field of a binary history file (magic, version, pool, sentence and word lengths,
+    /// word bytes) with its byte offset, hex dump and decoded value, e.g. to find out why the
+    /// file does not load.  For version 3 files, the compressed and the decompressed pools are
+    /// shown separately.  Exits with a non-zero status if the file ends early.
+    DumpStructure {
+        /// A history file in binary, possibly wrapped in gzip, xz or zstd.  Pass "-" to read from
+        /// stdin.  Defaults to the history of the input method specified by --ime.
+        path: Option<PathBuf>,
+
+        #[structopt(flatten)]
+        pager: PagerOpt,
+    },
+    /// Show the size, format version, sentence counts per pool and compression ratio of binary
+    /// history files, e.g. of a directory of backups, without loading their sentences.  Exits
+    /// with a non-zero status if any file cannot be read.
+    Info {
+        /// History files in binary, possibly wrapped in gzip, xz or zstd.  Pass "-" to read from
+        /// stdin.  Defaults to the history of the input method specified by --ime.
+        paths: Vec<PathBuf>,
+    },
+    /// List history files found in the fcitx5 data directory ($XDG_DATA_HOME/fcitx5), with their
+    /// formats and sentence counts.
+    Locate,
+    /// Manage snapshots of history files, taken every time before they are overwritten.
+    /// Snapshots are stored under $XDG_STATE_HOME/libime-history-merge/snapshots.
+    Snapshots(SnapshotsCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub enum SnapshotsCommand {
+    /// List snapshots with their sentence counts per pool, newest first.
+    List {
+        /// Only list snapshots taken from this history file.
+        source: Option<PathBuf>,
+    },
+    /// Restore a snapshot to the path it was taken from.
+    Restore {
+        /// Id of the snapshot to restore, a unique prefix of the id is also accepted.
+        id: String,
+
+        /// If present, write the snapshot to specified path instead of where it was taken
+        /// from.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct OutputOpt {
+    /// Write the resulting history to specified path, "-" writes to stdout if it is not a
+    /// terminal.
+    #[structopt(short, long, required_unless = "in-place")]
+    pub output: Option<PathBuf>,
+
+    /// If present, allow overwriting an existing output path.  The overwritten file is kept as a
+    /// timestamped backup next to it.
+    #[structopt(short, long)]
+    pub force: bool,
+
+    /// If present, write the result back to the first history file instead of to
+    /// -o|--output.  The original file is kept as a timestamped backup next to it.
+    #[structopt(short, long, conflicts_with = "output")]
+    pub in_place: bool,
+}
+
+impl OutputOpt {
+    /// Returns where to write the result, `first_input` being the path of the first input
+    /// history.
+    fn target(&self, first_input: &Path) -> Result<PathBuf> {
+        let path = match &self.output {
+            _ if self.in_place && is_stdio(first_input) => {
+                return Err(Error::LogicError(
+                    "Cannot write in place when reading from stdin".to_string(),
+                ))
+            }
+            _ if self.in_place => return Ok(first_input.to_path_buf()),
+            Some(path) => path.to_path_buf(),
+            None => {
+                return Err(Error::LogicError(
+                    "-o|--output is not specified".to_string(),
+                ))
+            }
+        };
+        check_overwrite(&path, self.force)?;
+        Ok(path)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DetectorOpt {
+    /// Also look for matches of a regular expression, given as "<name>=<regex>" or "<regex>".
+    /// Can be given multiple times.
+    #[structopt(short, long = "pattern", number_of_values = 1)]
+    pub patterns: Vec<String>,
+
+    /// If present, do not use the built-in detectors for email addresses, phone numbers, ID
+    /// numbers, bank card numbers and password-like strings.
+    #[structopt(long)]
+    pub no_builtin: bool,
+}
+
+impl DetectorOpt {
+    fn detectors(&self) -> Result<Vec<Detector>> {
+        let mut ret = if self.no_builtin {
+            Vec::new()
+        } else {
+            Detector::builtin()
+        };
+        for pattern in &self.patterns {
+            let detector = match pattern.split_once('=') {
+                Some((name, re))
+                    if !name.is_empty()
+                        && name
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+                {
+                    Detector::new(name, re)?
+                }
+                _ => Detector::new("custom", pattern)?,
+            };
+            ret.push(detector);
+        }
+        if ret.is_empty() {
+            return Err(Error::LogicError(
+                "No detectors to look for sensitive data with".to_string(),
+            ));
+        }
+        Ok(ret)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PagerOpt {
+    /// If present, do not invoke a pager (pager defaults to the environment variable $PAGER's
+    /// value).
+    #[structopt(short, long)]
+    pub no_pager: bool,
+}
+
+impl PagerOpt {
+    fn setup(&self) {
+        if !self.no_pager {
+            pager::Pager::with_default_pager("less").setup();
+        }
+    }
 }
 
 fn setup() -> Opt {
@@ -50,26 +654,953 @@ fn setup() -> Opt {
     Opt::from_args()
 }
 
-fn run() -> Result<()> {
-    let mut opts = setup();
+/// Checks if `path` stands for stdin or stdout.
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
 
-    let mut histories = vec![opts.user_history_path];
-    histories.append(&mut opts.more_paths);
-    let histories: Vec<History> = histories.iter().map(History::load).collect::<Result<_>>()?;
+fn check_overwrite(path: &Path, force: bool) -> Result<()> {
+    if !is_stdio(path) && path.exists() && !force {
+        Err(Error::IoError(
+            "Output path already exists, pass -f|--force to overwrite it".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
 
-    let merged = merge(histories, opts.weights)?;
+/// Reads the content of `path`, or of stdin if `path` is "-".
+fn read(path: &Path) -> Result<Vec<u8>> {
+    if is_stdio(path) {
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut content)?;
+        Ok(content)
+    } else {
+        Ok(std::fs::read(path)?)
+    }
+}
 
-    match opts.output {
-        Some(path) => {
-            if path.exists() {
-                return Err(Error::IoError("Output path already exists".to_string()));
+/// Returns the path of the history of the input method `ime`.
+fn default_path(ime: &str) -> Result<PathBuf> {
+    let path = locate::history_path(ime)?;
+    log::info!("Using history file '{}'", path.display());
+    Ok(path)
+}
+
+/// Returns `paths`, or the history of the input method `ime` if `paths` is empty.
+fn or_default(paths: Vec<PathBuf>, ime: &str) -> Result<Vec<PathBuf>> {
+    if paths.is_empty() {
+        Ok(vec![default_path(ime)?])
+    } else {
+        Ok(paths)
+    }
+}
+
+/// Returns `path`, or the history of the input method `ime` if `path` is not given.
+fn path_or_default(path: Option<PathBuf>, ime: &str) -> Result<PathBuf> {
+    match path {
+        Some(path) => Ok(path),
+        None => default_path(ime),
+    }
+}
+
+/// Returns the history to read (see [`path_or_default`]) and where to write the result:
+/// `output`, which is only overwritten with `force`, or else the history itself.
+fn path_and_target(
+    path: Option<PathBuf>,
+    output: Option<PathBuf>,
+    force: bool,
+    ime: &str,
+) -> Result<(PathBuf, PathBuf)> {
+    let path = path_or_default(path, ime)?;
+    let target = match output {
+        Some(output) => {
+            check_overwrite(&output, force)?;
+            output
+        }
+        None => path.clone(),
+    };
+    Ok((path, target))
+}
+
+/// Loads histories, dropping the sentences blocked by a blocklist from each of them.
+#[derive(Default)]
+struct Loader {
+    /// The blocklist and its path
+    blocklist: Option<(PathBuf, Blocklist)>,
+    /// Number of sentences the blocklist dropped so far
+    dropped: Cell<usize>,
+}
+
+impl Loader {
+    /// Makes a loader applying the blocklist specified by `opts`, which defaults to the one at
+    /// [`Blocklist::default_path`] if it exists.
+    fn new(opts: &Opt) -> Result<Self> {
+        if opts.no_blocklist {
+            return Ok(Loader::default());
+        }
+        let path = match &opts.blocklist {
+            Some(path) => path.clone(),
+            None => Blocklist::default_path()?,
+        };
+        if opts.blocklist.is_none() && !path.exists() {
+            return Ok(Loader::default());
+        }
+        let blocklist = Blocklist::load(&path)?;
+        Ok(Loader {
+            blocklist: Some((path, blocklist)),
+            dropped: Cell::new(0),
+        })
+    }
+
+    fn load(&self, path: &Path) -> Result<History> {
+        let mut history = if is_stdio(path) {
+            History::load_from_reader(std::io::stdin().lock())?
+        } else {
+            History::load(path)?
+        };
+        self.apply(&mut history);
+        Ok(history)
+    }
+
+    /// Same as [`Loader::load`], but for a plain-text history, see [`History::load_from_text`].
+    fn load_text(&self, path: &Path) -> Result<History> {
+        let mut history = History::load_from_text(&read(path)?)?;
+        self.apply(&mut history);
+        Ok(history)
+    }
+
+    /// Drops the sentences blocked by the blocklist from `history`, e.g. from a history that is
+    /// not loaded from a file.
+    fn apply(&self, history: &mut History) {
+        if let Some((_, blocklist)) = &self.blocklist {
+            self.dropped
+                .set(self.dropped.get() + blocklist.apply(history));
+        }
+    }
+
+    fn load_all(&self, paths: &[PathBuf]) -> Result<Vec<History>> {
+        if paths.iter().filter(|path| is_stdio(path)).count() > 1 {
+            return Err(Error::LogicError(
+                "Stdin (\"-\") can only be read once".to_string(),
+            ));
+        }
+        paths.iter().map(|path| self.load(path)).collect()
+    }
+
+    /// Logs how many sentences the blocklist dropped, if there is one.
+    fn report(&self) {
+        if let Some((path, _)) = &self.blocklist {
+            log::info!(
+                "Blocklist '{}' dropped {} sentence(s)",
+                path.display(),
+                self.dropped.get()
+            );
+        }
+    }
+}
+
+/// Saves `history` to `path`, or writes it to stdout if `path` is "-".  If `path` already
+/// exists, its current content is put into the snapshot store and kept as a timestamped backup
+/// next to it before being overwritten.
+fn save(history: &History, path: &Path) -> Result<()> {
+    if is_stdio(path) {
+        return write_stdout(history);
+    }
+    if path.exists() {
+        let snapshot = SnapshotStore::open_default()?.take(path)?;
+        log::info!("Saved snapshot '{}' of '{}'", snapshot.id, path.display());
+    }
+    if let Some(backup) = history.save_with_backup(path)? {
+        log::info!("Backed up '{}' to '{}'", path.display(), backup.display());
+    }
+    Ok(())
+}
+
+/// Same as [`save`], but does not keep a snapshot or a backup of what `path` held.
+fn save_without_backup(history: &History, path: &Path) -> Result<()> {
+    if is_stdio(path) {
+        write_stdout(history)
+    } else {
+        history.save(path)
+    }
+}
+
+fn write_stdout(history: &History) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    if stdout.is_terminal() {
+        return Err(Error::LogicError(
+            "Refusing to write a binary history to a terminal".to_string(),
+        ));
+    }
+    stdout.write_all(&to_bytes(history)?)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Deletes the backups and snapshots of the history file at `path` if `purge` is set, otherwise
+/// warns that they exist.
+fn purge_copies(path: &Path, purge: bool) -> Result<()> {
+    let mut copies = History::backups(path)?;
+    copies.extend(
+        SnapshotStore::open_default()?
+            .list_for(path)?
+            .into_iter()
+            .map(|snapshot| snapshot.path),
+    );
+    for copy in &copies {
+        if purge {
+            std::fs::remove_file(copy)?;
+            log::info!("Deleted '{}'", copy.display());
+        } else {
+            log::warn!("'{}' may still hold sensitive data", copy.display());
+        }
+    }
+    if !purge && !copies.is_empty() {
+        log::warn!(
+            "Pass --purge to delete these {} backup(s) and snapshot(s)",
+            copies.len()
+        );
+    }
+    Ok(())
+}
+
+/// Prints `question` and returns the first character of the answer, lowercased, or `default`
+/// if the answer is empty.  Returns `None` if stdin is not a terminal.
+fn ask(question: &str, default: char) -> Result<Option<char>> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(None);
+    }
+    eprint!("{} ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    Ok(Some(
+        answer
+            .trim()
+            .chars()
+            .next()
+            .map_or(default, |c| c.to_ascii_lowercase()),
+    ))
+}
+
+/// Lets the user edit `history` in an editor.  If the edited text does not parse, the error is
+/// shown and the editor can be re-opened with the edits intact.  Before the edited history is
+/// returned, the numbers of removed and added sentences are shown for confirmation.
+///
+/// The edited text must keep a pool marker for each of libime's pools.  A history with other
+/// pools, e.g. one loaded from plain text, is laid out in libime's pools before it is edited.
+fn edit(history: &History) -> Result<History> {
+    let aborted = || Error::LogicError("Edit aborted, nothing is saved".to_string());
+    let mut laid_out = history.clone();
+    if laid_out.check_pools().is_err() {
+        let dropped = laid_out.lay_out();
+        if !dropped.is_empty() {
+            log::warn!(
+                "{} oldest sentence(s) do not fit into libime's pools and are dropped",
+                dropped.len()
+            );
+        }
+    }
+    let mut buffer = laid_out.to_annotated_text().into_bytes();
+    loop {
+        buffer = edit::edit_bytes(&buffer)?;
+        let edited = History::load_from_annotated_text(&buffer).and_then(|edited| {
+            match edited.check_pools() {
+                Ok(()) => Ok(edited),
+                Err(_) => Err(Error::LogicError(format!(
+                    "The edited text marks {} pool(s), keep a \"{} <n>\" line for each of the {} \
+                     pools",
+                    edited.pools.len(),
+                    POOL_MARKER,
+                    laid_out.pools.len()
+                ))),
+            }
+        });
+        let edited = match edited {
+            Ok(edited) => edited,
+            Err(e) => {
+                log::error!("{}", e);
+                match ask("Re-open the editor with your changes? [Y/n]", 'y')? {
+                    Some('y') => continue,
+                    _ => return Err(aborted()),
+                }
+            }
+        };
+        let diff = history.diff(&edited);
+        log::info!(
+            "{} sentence(s) removed, {} sentence(s) added",
+            diff.removed.len(),
+            diff.added.len()
+        );
+        match ask("Keep the edited history? [Y]es/[e]dit again/[a]bort", 'y')? {
+            Some('y') | None => return Ok(edited),
+            Some('e') => continue,
+            _ => return Err(aborted()),
+        }
+    }
+}
+
+fn run_stats(loader: &Loader, paths: &[PathBuf], top: usize, json: bool) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct FileStats<'a> {
+        path: &'a Path,
+        #[serde(flatten)]
+        stats: Stats,
+    }
+
+    let stats: Vec<FileStats> = paths
+        .iter()
+        .zip(loader.load_all(paths)?)
+        .map(|(path, history)| FileStats {
+            path,
+            stats: history.stats(top),
+        })
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        for (i, file) in stats.iter().enumerate() {
+            if i > 0 {
+                println!();
             }
-            let merged = if opts.edit {
-                History::load_from_text(edit::edit(merged.to_string())?.as_bytes())?
+            println!("{}", file.path.display());
+            print!("{}", file.stats);
+        }
+    }
+    Ok(())
+}
+
+fn run_search(loader: &Loader, pattern: &Pattern, paths: &[PathBuf], context: usize) -> Result<()> {
+    for (path, history) in paths.iter().zip(loader.load_all(paths)?) {
+        // The last printed sentence, so that overlapping neighbourhoods are printed once.
+        let mut printed: Option<Location> = None;
+        for (location, _) in history.search(pattern) {
+            let start = location.index.saturating_sub(context);
+            if context > 0
+                && printed.is_some_and(|p| p.pool != location.pool || p.index + 1 < start)
+            {
+                println!("--");
+            }
+            for index in start..=location.index + context {
+                let here = Location {
+                    pool: location.pool,
+                    index,
+                };
+                if printed.is_some_and(|p| p.pool == here.pool && p.index >= index) {
+                    continue;
+                }
+                let sentence = match history.get(here) {
+                    Some(sentence) => sentence,
+                    None => break,
+                };
+                let sep = if pattern.is_match(sentence) { ':' } else { '-' };
+                println!(
+                    "{}{sep}{}{sep}{}{sep} {}",
+                    path.display(),
+                    here.pool,
+                    here.index,
+                    sentence,
+                    sep = sep,
+                );
+                printed = Some(here);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_predict(
+    loader: &Loader,
+    path: &Path,
+    words: &[String],
+    top: usize,
+    sentences: bool,
+) -> Result<()> {
+    let history = loader.load(path)?;
+    let context: Vec<&str> = words.iter().map(String::as_str).collect();
+    let predictions = history.predict(&context);
+    if predictions.is_empty() {
+        log::info!(
+            "Nothing follows '{}' in '{}'",
+            words.join(" "),
+            path.display()
+        );
+        return Ok(());
+    }
+    println!("{:>10} {:>10}  word", "weight", "score");
+    for prediction in predictions.iter().take(top) {
+        println!("{}", prediction);
+        if sentences {
+            for cause in &prediction.causes {
+                println!("{:>23}{}", "", cause);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_check(paths: &[PathBuf]) -> Result<()> {
+    let mut failed = 0;
+    for path in paths {
+        let report = check::check(&read(path)?);
+        if report.passed() {
+            println!("{}: ok", path.display());
+        } else {
+            println!("{}: FAILED", path.display());
+            failed += 1;
+        }
+        for line in report.to_string().lines() {
+            println!("  {}", line);
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(Error::LogicError(format!(
+            "{} of {} file(s) failed the check",
+            failed,
+            paths.len()
+        ))),
+    }
+}
+
+fn run_info(paths: &[PathBuf]) -> Result<()> {
+    let mut failed = 0;
+    for path in paths {
+        let info = match is_stdio(path) {
+            true => read(path).and_then(|content| Info::read(std::io::Cursor::new(content))),
+            false => Info::load(path),
+        };
+        match info {
+            Ok(info) => println!("{}: {}", path.display(), info),
+            Err(e) => {
+                log::error!("{}", e);
+                failed += 1;
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(Error::LogicError(format!(
+            "{} of {} file(s) could not be read",
+            failed,
+            paths.len()
+        ))),
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(loader: &Loader, path: &Path, target: &Path) -> Result<()> {
+    if is_stdio(path) || is_stdio(target) {
+        return Err(Error::LogicError(
+            "The terminal browser only works on history files, not on stdin or stdout".to_string(),
+        ));
+    }
+    if !std::io::stdout().is_terminal() {
+        return Err(Error::LogicError(
+            "The terminal browser needs stdout to be a terminal".to_string(),
+        ));
+    }
+    let history = loader.load(path)?;
+    // Only the content from before the first save is worth a snapshot and a backup, later saves
+    // overwrite the target as is.
+    let mut saved = false;
+    tui::browse(history, &path.display().to_string(), |history| {
+        if saved || !target.exists() {
+            history.save(target)?;
+            saved = true;
+            return Ok(format!("Saved to '{}'", target.display()));
+        }
+        let snapshot = SnapshotStore::open_default()?.take(target)?;
+        let backup = history.save_with_backup(target)?;
+        saved = true;
+        Ok(format!(
+            "Saved to '{}', previous content kept as snapshot '{}'{}",
+            target.display(),
+            snapshot.id,
+            backup.map_or(String::new(), |backup| format!(
+                " and in '{}'",
+                backup.display()
+            )),
+        ))
+    })
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_loader: &Loader, _path: &Path, _target: &Path) -> Result<()> {
+    Err(Error::LogicError(
+        "This build does not include the terminal browser, rebuild with the \"tui\" feature"
+            .to_string(),
+    ))
+}
+
+fn run_locate() -> Result<()> {
+    for path in locate::find_histories()? {
+        let content = std::fs::read(&path)?;
+        let format = match History::detect_format(&content) {
+            Format::Binary if content.len() >= 8 => format!(
+                "{} v{}",
+                Format::Binary,
+                u32::from_be_bytes(content[4..8].try_into().unwrap()),
+            ),
+            format => format.to_string(),
+        };
+        let sentences = match History::parse(&content) {
+            Ok(history) => format!("{} sentences", history.get_sentences().len()),
+            Err(e) => format!("unreadable ({})", e),
+        };
+        println!("{}\t{}\t{}", path.display(), format, sentences);
+    }
+    Ok(())
+}
+
+fn run_snapshots(command: SnapshotsCommand) -> Result<()> {
+    let store = SnapshotStore::open_default()?;
+    match command {
+        SnapshotsCommand::List { source } => {
+            let snapshots = match source {
+                Some(source) => store.list_for(source)?,
+                None => store.list()?,
+            };
+            for snapshot in snapshots {
+                let counts = match snapshot.load() {
+                    Ok(history) => history
+                        .pools
+                        .iter()
+                        .map(|pool| pool.0.len().to_string())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    Err(e) => format!("unreadable ({})", e),
+                };
+                println!("{}\t{}\t{}", snapshot.id, counts, snapshot.source.display());
+            }
+        }
+        SnapshotsCommand::Restore { id, output } => {
+            let snapshot = store.get(&id)?;
+            let target = output.unwrap_or_else(|| snapshot.source.clone());
+            save(&snapshot.load()?, &target)?;
+            log::info!(
+                "Restored snapshot '{}' to '{}'",
+                snapshot.id,
+                target.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_command(command: Command, ime: &str, loader: &Loader) -> Result<()> {
+    match command {
+        Command::Inspect { paths, json, pager } => {
+            let histories = loader.load_all(&or_default(paths, ime)?)?;
+            pager.setup();
+            for history in histories {
+                if json {
+                    println!("{}", history.to_json()?);
+                } else {
+                    println!("{}", history);
+                }
+            }
+        }
+        Command::Merge {
+            paths,
+            weights,
+            edit: edit_merged,
+            output,
+        } => {
+            let target = output.target(&paths[0])?;
+            let merged = merge(loader.load_all(&paths)?, weights)?;
+            let merged = if edit_merged { edit(&merged)? } else { merged };
+            save(&merged, &target)?;
+        }
+        Command::Compile { path, output } => {
+            let target = output.target(&path)?;
+            let mut history = loader.load_text(&path)?;
+            let dropped = history.lay_out();
+            if !dropped.is_empty() {
+                log::warn!(
+                    "{} oldest sentence(s) do not fit into libime's pools and are dropped",
+                    dropped.len()
+                );
+            }
+            save(&history, &target)?;
+        }
+        Command::Seed {
+            corpus,
+            lexicon,
+            output,
+            force,
+        } => {
+            check_overwrite(&output, force)?;
+            if corpus.iter().filter(|path| is_stdio(path)).count() > 1 {
+                return Err(Error::LogicError(
+                    "Stdin (\"-\") can only be read once".to_string(),
+                ));
+            }
+            let lexicon = Lexicon::load(&lexicon)?;
+            if lexicon.is_empty() {
+                return Err(Error::LogicError("The lexicon has no words".to_string()));
+            }
+            log::info!("Loaded {} word(s) from lexicon", lexicon.len());
+            let mut text = String::new();
+            for path in &corpus {
+                text.push_str(&String::from_utf8(read(path)?)?);
+                text.push('\n');
+            }
+            let mut history = seed::seed(&text, &lexicon);
+            loader.apply(&mut history);
+            log::info!(
+                "Generated {} sentence(s) in pools of {}",
+                history.get_sentences().len(),
+                history
+                    .pools
+                    .iter()
+                    .map(|pool| pool.0.len().to_string())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            );
+            save(&history, &output)?;
+        }
+        Command::Diff { old, new, pager } => {
+            let [old, new]: [History; 2] = loader
+                .load_all(&[old, new])?
+                .try_into()
+                .expect("two histories are loaded");
+            let diff = old.diff(&new);
+            pager.setup();
+            if !diff.is_empty() {
+                println!("{}", diff);
+            }
+        }
+        Command::Impact {
+            old,
+            new,
+            top,
+            pager,
+        } => {
+            let [old, new]: [History; 2] = loader
+                .load_all(&[old, new])?
+                .try_into()
+                .expect("two histories are loaded");
+            let mut impact = old.impact(&new);
+            impact.transitions.truncate(top);
+            pager.setup();
+            print!("{}", impact);
+        }
+        Command::Search {
+            pattern,
+            paths,
+            regex,
+            sentence,
+            context,
+        } => {
+            let scope = if sentence {
+                Scope::Sentence
+            } else {
+                Scope::Word
+            };
+            let pattern = if regex {
+                Pattern::regex(&pattern, scope)?
+            } else {
+                Pattern::literal(&pattern, scope)
+            };
+            run_search(loader, &pattern, &or_default(paths, ime)?, context)?;
+        }
+        Command::Filter {
+            path,
+            regexes,
+            words,
+            longer_than,
+            shorter_than,
+            listed_in,
+            dry_run,
+            output,
+            force,
+        } => {
+            let filter = Filter {
+                patterns: regexes
+                    .iter()
+                    .map(|re| Pattern::regex(re, Scope::Sentence))
+                    .collect::<Result<_>>()?,
+                words: words.into_iter().collect(),
+                longer_than,
+                shorter_than,
+                sentences: match listed_in {
+                    Some(listed_in) => History::load_from_text(&read(&listed_in)?)?
+                        .get_sentences()
+                        .into_iter()
+                        .collect(),
+                    None => Default::default(),
+                },
+            };
+            if filter.is_empty() {
+                return Err(Error::LogicError(
+                    "No criteria given, nothing to remove".to_string(),
+                ));
+            }
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            let mut history = loader.load(&path)?;
+            let removed = history.remove_matching(&filter);
+            if dry_run {
+                for (location, sentence) in &removed {
+                    println!("{}: {}", location, sentence);
+                }
+                log::info!("{} sentence(s) would be removed", removed.len());
+            } else if removed.is_empty() {
+                log::info!("No sentences match, nothing is written");
+            } else {
+                log::info!("Removing {} sentence(s)", removed.len());
+                save(&history, &target)?;
+            }
+        }
+        Command::Trim {
+            path,
+            keep,
+            drop_from,
+            clear_pools,
+            dry_run,
+            output,
+            force,
+        } => {
+            if keep.is_none() && drop_from.is_none() && clear_pools.is_empty() {
+                return Err(Error::LogicError(
+                    "No criteria given, nothing to remove".to_string(),
+                ));
+            }
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            let mut history = loader.load(&path)?;
+            let mut removed = Vec::new();
+            for pool in clear_pools {
+                removed.append(&mut history.clear_pool(pool));
+            }
+            if let Some(location) = drop_from {
+                removed.append(&mut history.drop_from(location));
+            }
+            if let Some(n) = keep {
+                removed.append(&mut history.keep_newest(n));
+            }
+            removed.sort_by_key(|(location, _)| *location);
+            if dry_run {
+                for (location, sentence) in &removed {
+                    println!("{}: {}", location, sentence);
+                }
+                log::info!("{} sentence(s) would be removed", removed.len());
+            } else if removed.is_empty() {
+                log::info!("Nothing to remove, nothing is written");
+            } else {
+                log::info!("Removing {} sentence(s)", removed.len());
+                save(&history, &target)?;
+            }
+        }
+        Command::Replace {
+            from,
+            to,
+            path,
+            regex,
+            diff,
+            dry_run,
+            output,
+            force,
+        } => {
+            let replacement = if regex {
+                Replacement::regex(&from, &to)?
+            } else {
+                Replacement::exact(&from, &to)
+            };
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            let original = loader.load(&path)?;
+            let mut history = original.clone();
+            let affected = history.replace_words(&replacement);
+            if diff {
+                let diff = original.diff(&history);
+                if !diff.is_empty() {
+                    println!("{}", diff);
+                }
+            }
+            log::info!("{} sentence(s) affected", affected);
+            if !dry_run && affected > 0 {
+                save(&history, &target)?;
+            }
+        }
+        Command::Promote {
+            phrases,
+            path,
+            boost,
+            dry_run,
+            output,
+            force,
+        } => {
+            let phrases = promote::parse_phrases(&String::from_utf8(read(&phrases)?)?);
+            if phrases.is_empty() {
+                return Err(Error::LogicError("No phrases to promote".to_string()));
+            }
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            let mut history = loader.load(&path)?;
+            let dropped = match boost {
+                Some(copies) => {
+                    log::info!("Boosting {} phrase(s) by {} copies", phrases.len(), copies);
+                    history.boost(&phrases, copies)
+                }
+                None => {
+                    log::info!("Promoting {} phrase(s)", phrases.len());
+                    history.promote(&phrases)
+                }
+            };
+            for sentence in &dropped {
+                log::debug!("dropped: {}", sentence);
+            }
+            log::info!(
+                "{} oldest sentence(s) no longer fit and are dropped",
+                dropped.len()
+            );
+            if !dry_run {
+                save(&history, &target)?;
+            }
+        }
+        Command::Scan { paths, detectors } => {
+            let detectors = detectors.detectors()?;
+            let paths = or_default(paths, ime)?;
+            let mut found = 0;
+            for (path, history) in paths.iter().zip(loader.load_all(&paths)?) {
+                for finding in history.scan(&detectors) {
+                    println!("{}:{}", path.display(), finding);
+                    found += 1;
+                }
+            }
+            if found > 0 {
+                return Err(Error::LogicError(format!(
+                    "Found {} piece(s) of sensitive data",
+                    found
+                )));
+            }
+        }
+        Command::Redact {
+            path,
+            detectors,
+            remove,
+            dry_run,
+            output,
+            force,
+            purge,
+        } => {
+            let detectors = detectors.detectors()?;
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            let mut history = loader.load(&path)?;
+            if dry_run {
+                for finding in history.scan(&detectors) {
+                    println!("{}", finding);
+                }
+                return Ok(());
+            }
+            let how = if remove {
+                Redaction::Remove
             } else {
-                merged
+                Redaction::Mask
             };
-            merged.save(&path)?;
+            let affected = history.redact(&detectors, how);
+            log::info!("Redacted {} sentence(s)", affected);
+            if affected > 0 {
+                save_without_backup(&history, &target)?;
+            }
+            if !is_stdio(&path) {
+                purge_copies(&path, purge)?;
+            }
+        }
+        Command::Stats { paths, top, json } => {
+            run_stats(loader, &or_default(paths, ime)?, top, json)?
+        }
+        Command::Predict {
+            words,
+            path,
+            top,
+            sentences,
+        } => {
+            let path = path_or_default(path, ime)?;
+            run_predict(loader, &path, &words, top, sentences)?;
+        }
+        Command::Arpa {
+            path,
+            output,
+            force,
+        } => {
+            let path = path_or_default(path, ime)?;
+            let arpa = loader.load(&path)?.to_arpa()?;
+            match output {
+                Some(output) if !is_stdio(&output) => {
+                    check_overwrite(&output, force)?;
+                    std::fs::write(&output, arpa.to_string())?;
+                    log::info!("Wrote language model to '{}'", output.display());
+                }
+                _ => print!("{}", arpa),
+            }
+        }
+        Command::Edit {
+            path,
+            output,
+            force,
+        } => {
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            save(&edit(&loader.load(&path)?)?, &target)?;
+        }
+        Command::Tui {
+            path,
+            output,
+            force,
+        } => {
+            let (path, target) = path_and_target(path, output, force, ime)?;
+            run_tui(loader, &path, &target)?;
+        }
+        Command::Check { paths } => run_check(&or_default(paths, ime)?)?,
+        Command::DumpStructure { path, pager } => {
+            let path = path_or_default(path, ime)?;
+            let structure = structure::dump_structure(&read(&path)?);
+            pager.setup();
+            print!("{}", structure);
+            if let Some(error) = structure.error {
+                return Err(Error::DeserializeError(format!(
+                    "Could not read '{}' to its end: {}",
+                    path.display(),
+                    error
+                )));
+            }
+        }
+        Command::Info { paths } => run_info(&or_default(paths, ime)?)?,
+        Command::Locate => run_locate()?,
+        Command::Snapshots(command) => run_snapshots(command)?,
+    }
+    Ok(())
+}
+
+/// The flat invocation from before subcommands existed, kept as a compatibility alias.
+fn run_legacy(mut opts: Opt, loader: &Loader) -> Result<()> {
+    let user_history_path = match opts.user_history_path {
+        Some(path) => path,
+        None => default_path(&opts.ime)?,
+    };
+
+    let output = if opts.in_place {
+        if is_stdio(&user_history_path) {
+            return Err(Error::LogicError(
+                "Cannot write in place when reading from stdin".to_string(),
+            ));
+        }
+        Some(user_history_path.clone())
+    } else {
+        opts.output
+    };
+
+    let mut histories = vec![user_history_path];
+    histories.append(&mut opts.more_paths);
+    let histories = loader.load_all(&histories)?;
+
+    let merged = merge(histories, opts.weights)?;
+
+    match output {
+        Some(path) => {
+            check_overwrite(&path, opts.force || opts.in_place)?;
+            let merged = if opts.edit { edit(&merged)? } else { merged };
+            save(&merged, &path)?;
         }
         None => {
             if opts.edit {
@@ -77,9 +1608,10 @@ fn run() -> Result<()> {
                     "-o|--output is not specified, the edited history will be lost".to_string(),
                 ));
             }
-            if !opts.no_pager && opts.output.is_none() {
-                pager::Pager::with_default_pager("less").setup();
+            PagerOpt {
+                no_pager: opts.no_pager,
             }
+            .setup();
             println!("{}", merged);
         }
     }
@@ -87,6 +1619,18 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+fn run() -> Result<()> {
+    let mut opts = setup();
+    let loader = Loader::new(&opts)?;
+
+    let ret = match opts.command.take() {
+        Some(command) => run_command(command, &opts.ime, &loader),
+        None => run_legacy(opts, &loader),
+    };
+    loader.report();
+    ret
+}
+
 fn main() {
     if let Err(e) = run() {
         log::error!("{}", e);
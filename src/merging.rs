@@ -1,3 +1,4 @@
+use crate::blocklist::Blocklist;
 use crate::data::{History, Pool, Sentence};
 use crate::utils::{gcd, split_vec};
 use crate::{Error, Result};
@@ -9,7 +10,7 @@ use crate::{Error, Result};
 /// REF: <https://github.com/fcitx/libime/blob/2e90224d4905c9228c4008bca52155829d673532/src/libime/core/historybigram.cpp#L392-L396>
 ///
 /// [libime]: https://github.com/fcitx/libime
-const POOL_SIZE: &[usize] = &[128, 8192, 65536];
+pub(crate) const POOL_SIZE: &[usize] = &[128, 8192, 65536];
 
 #[derive(Debug)]
 struct WeightedHistory<'a> {
@@ -137,7 +138,7 @@ pub fn merge(histories: Vec<History>, weights: Vec<u8>) -> Result<History> {
             "Number of weights should match number of histories".to_string(),
         ));
     }
-    if weights.iter().any(|w| *w == 0u8) {
+    if weights.contains(&0u8) {
         return Err(Error::LogicError("Zero weight is not allowed".to_string()));
     }
 
@@ -165,6 +166,20 @@ pub fn merge(histories: Vec<History>, weights: Vec<u8>) -> Result<History> {
     Ok(History::new(pools))
 }
 
+/// Same as [`merge`], but first removes the sentences blocked by `blocklist` from each of the
+/// `histories`.  Returns the merged history and the number of removed sentences.
+pub fn merge_with(
+    mut histories: Vec<History>,
+    weights: Vec<u8>,
+    blocklist: &Blocklist,
+) -> Result<(History, usize)> {
+    let dropped = histories
+        .iter_mut()
+        .map(|history| blocklist.apply(history))
+        .sum();
+    Ok((merge(histories, weights)?, dropped))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
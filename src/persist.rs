@@ -0,0 +1,204 @@
+use std::{
+    fs::{self, File, OpenOptions, Permissions},
+    io::Write,
+    os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use crate::{utils::timestamp, Result};
+
+/// Mode of newly created history files, matching what [`libime`][libime] uses for
+/// `user.history`.
+///
+/// [libime]: https://github.com/fcitx/libime
+const DEFAULT_MODE: u32 = 0o600;
+
+/// Returns the directory containing `path`, treating a bare file name as relative to the current
+/// directory.
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Writes `content` to `path` atomically.  The content is first written to a temporary file in the
+/// same directory and flushed to disk, then renamed over `path`, so that readers of `path` either
+/// see the old content or the new content, never a partially written file.
+///
+/// If `path` already exists, its permission bits and ownership are carried over to the new file,
+/// otherwise the new file is created with mode `0600`.  If `path` is a symbolic link, the file it
+/// points to is replaced and the link is kept.
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let resolved = fs::canonicalize(path).ok();
+    let path = resolved.as_deref().unwrap_or(path);
+    let dir = parent_dir(path);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let original = fs::metadata(path).ok();
+
+    let write_tmp = || -> Result<()> {
+        let mut tmp = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(DEFAULT_MODE)
+            .open(&tmp_path)?;
+        tmp.write_all(content)?;
+        if let Some(meta) = &original {
+            tmp.set_permissions(Permissions::from_mode(meta.mode() & 0o7777))?;
+            if let Err(e) = std::os::unix::fs::fchown(&tmp, Some(meta.uid()), Some(meta.gid())) {
+                log::warn!(
+                    "Could not preserve ownership of '{}': {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+        tmp.sync_all()?;
+        Ok(())
+    };
+    if let Err(e) = write_tmp().and_then(|_| Ok(fs::rename(&tmp_path, path)?)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    // Persist the rename itself
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Copies `path` to a sibling file named `<file name>.<timestamp>.bak` and returns the path of the
+/// copy.  Permissions of the original are kept on the copy.
+pub(crate) fn backup(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stamp = timestamp();
+    let mut backup_path = path.with_file_name(format!("{}.{}.bak", file_name, stamp));
+    let mut n = 1;
+    while backup_path.exists() {
+        backup_path = path.with_file_name(format!("{}.{}-{}.bak", file_name, stamp, n));
+        n += 1;
+    }
+    fs::copy(path, &backup_path)?;
+    Ok(backup_path)
+}
+
+/// Checks if `suffix` is what [`backup`] puts between the file name and `.bak`: a timestamp
+/// `YYYYmmddTHHMMSSZ`, optionally followed by `-<n>`.
+fn is_backup_suffix(suffix: &str) -> bool {
+    let (stamp, n) = match suffix.split_once('-') {
+        Some((stamp, n)) => (stamp, Some(n)),
+        None => (suffix, None),
+    };
+    let digits = |s: &[u8]| !s.is_empty() && s.iter().all(u8::is_ascii_digit);
+    let stamp = stamp.as_bytes();
+    stamp.len() == 16
+        && digits(&stamp[..8])
+        && stamp[8] == b'T'
+        && digits(&stamp[9..15])
+        && stamp[15] == b'Z'
+        && n.is_none_or(|n| digits(n.as_bytes()))
+}
+
+/// Lists the backups of `path` made by [`backup`], sorted by file name.  Only files named
+/// exactly like [`backup`] names them are listed, so other files such as `<file name>.old.bak`
+/// or backups of `<file name>.gz` are not.
+pub(crate) fn backups(path: &Path) -> Result<Vec<PathBuf>> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let prefix = format!("{}.", file_name);
+    let dir = parent_dir(path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut ret = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        let suffix = name
+            .strip_prefix(&prefix)
+            .and_then(|name| name.strip_suffix(".bak"));
+        if suffix.is_some_and(is_backup_suffix) {
+            ret.push(path.with_file_name(name.as_ref()));
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{backup, backups, write_atomic};
+    use crate::Result;
+
+    #[test]
+    fn new_file_is_private() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("user.history");
+        write_atomic(&path, b"content")?;
+        assert_eq!(fs::read(&path)?, b"content");
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        Ok(())
+    }
+
+    #[test]
+    fn overwrite_keeps_mode_and_leaves_no_temp_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("user.history");
+        fs::write(&path, b"old")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640))?;
+        write_atomic(&path, b"new")?;
+        assert_eq!(fs::read(&path)?, b"new");
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o640);
+        assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn overwrite_keeps_symlink() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let real = dir.path().join("synced.history");
+        let link = dir.path().join("user.history");
+        fs::write(&real, b"old")?;
+        std::os::unix::fs::symlink(&real, &link)?;
+        write_atomic(&link, b"new")?;
+        assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+        assert_eq!(fs::read(&real)?, b"new");
+        Ok(())
+    }
+
+    #[test]
+    fn backups_do_not_collide() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("user.history");
+        fs::write(&path, b"old")?;
+        let first = backup(&path)?;
+        let second = backup(&path)?;
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&first)?, b"old");
+        assert_eq!(fs::read(&second)?, b"old");
+        for other in [
+            "other.history.20260101T000000Z.bak",
+            "user.history.old.bak",
+            "user.history.gz.20250101T000000Z.bak",
+            "user.history.20250101T000000Z-x.bak",
+        ] {
+            fs::write(dir.path().join(other), b"other")?;
+        }
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(backups(&path)?, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    data::{History, Sentence},
+    search::Location,
+};
+
+/// A sentence that contributes to a [`Prediction`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cause {
+    pub location: Location,
+    pub sentence: Sentence,
+}
+
+/// A word that follows the queried words somewhere in a history, see [`History::predict`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prediction {
+    pub word: String,
+    /// Number of times the word follows the queried words, each occurrence weighted by the
+    /// weight of its pool (see [`HistoryBigram`](crate::bigram::HistoryBigram))
+    pub weight: f32,
+    /// [`HistoryBigram::score`](crate::bigram::HistoryBigram::score) of the last queried word
+    /// followed by this word
+    pub score: f32,
+    /// Sentences in which the word follows the queried words, newest first
+    pub causes: Vec<Cause>,
+}
+
+impl History {
+    /// Lists words that directly follow the sequence of words `context` inside the sentences of
+    /// this history, most likely first.  Empty words are skipped, and an empty `context` yields
+    /// no predictions.
+    pub fn predict(&self, context: &[&str]) -> Vec<Prediction> {
+        let last = match context.last() {
+            Some(last) => *last,
+            None => return Vec::new(),
+        };
+        let model = self.bigram();
+
+        let mut found: HashMap<&str, Prediction> = HashMap::new();
+        for (i, (pool, weight)) in self.pools.iter().zip(&model.pool_weights).enumerate() {
+            for (j, sentence) in pool.0.iter().enumerate() {
+                let words: Vec<&str> = sentence
+                    .0
+                    .iter()
+                    .filter(|word| !word.is_empty())
+                    .map(|word| word.0.as_str())
+                    .collect();
+                for window in words.windows(context.len() + 1) {
+                    let (prefix, word) = window.split_at(context.len());
+                    if prefix != context {
+                        continue;
+                    }
+                    let prediction = found.entry(word[0]).or_insert_with(|| Prediction {
+                        word: word[0].to_string(),
+                        weight: 0.0,
+                        score: model.score(last, word[0]),
+                        causes: Vec::new(),
+                    });
+                    prediction.weight += weight;
+                    let location = Location { pool: i, index: j };
+                    if prediction
+                        .causes
+                        .last()
+                        .is_none_or(|cause| cause.location != location)
+                    {
+                        prediction.causes.push(Cause {
+                            location,
+                            sentence: sentence.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut ret: Vec<Prediction> = found.into_values().collect();
+        ret.sort_by(|lhs, rhs| {
+            rhs.weight
+                .total_cmp(&lhs.weight)
+                .then_with(|| lhs.word.cmp(&rhs.word))
+        });
+        ret
+    }
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.sentence)
+    }
+}
+
+impl Display for Prediction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>10.4} {:>10.4}  {}",
+            self.weight, self.score, self.word
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{data::History, test_util::history};
+
+    fn sample() -> History {
+        history(vec![
+            vec!["我 喜欢 音乐", "喜欢 你"],
+            vec!["我 喜欢 你 喜欢 你"],
+        ])
+    }
+
+    #[test]
+    fn single_word() {
+        let predictions = sample().predict(&["喜欢"]);
+        assert_eq!(
+            predictions
+                .iter()
+                .map(|p| (p.word.as_str(), p.causes.len()))
+                .collect::<Vec<_>>(),
+            vec![("你", 2), ("音乐", 1)]
+        );
+        // Pools weigh 2/3 and 1/3, "你" follows "喜欢" once in pool 0 and twice in pool 1.
+        assert!((predictions[0].weight - 4.0 / 3.0).abs() < 1e-6);
+        assert!((predictions[1].weight - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(predictions[0].causes[0].to_string(), "0:1: 喜欢 你");
+        assert!(predictions[0].score > predictions[1].score);
+    }
+
+    #[test]
+    fn sequence() {
+        let history = sample();
+        let predictions = history.predict(&["我", "喜欢"]);
+        assert_eq!(
+            predictions
+                .iter()
+                .map(|p| p.word.as_str())
+                .collect::<Vec<_>>(),
+            vec!["音乐", "你"]
+        );
+        assert!(history.predict(&["你", "我"]).is_empty());
+        assert!(history.predict(&[]).is_empty());
+    }
+}
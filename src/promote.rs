@@ -0,0 +1,132 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::data::{History, Sentence, Word};
+
+/// Parses a list of phrases, one per line with words separated by spaces, e.g. "项目 代号".
+/// Empty lines and lines starting with "#" are ignored, and repeated phrases are kept once.
+pub fn parse_phrases(content: &str) -> Vec<Sentence> {
+    let mut seen = HashSet::new();
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            Sentence(
+                line.split_whitespace()
+                    .map(String::from)
+                    .map(Word)
+                    .collect(),
+            )
+        })
+        .filter(|sentence| seen.insert(sentence.clone()))
+        .collect()
+}
+
+impl History {
+    /// Puts `sentences` at the newest end of pool 0, in the given order, so that the input
+    /// method ranks them as recently typed.  Every occurrence of them elsewhere in the history is
+    /// moved there, and those not in the history are added once.  The pools are then
+    /// [rebalanced](History::rebalance), and the sentences that no longer fit are returned.
+    pub fn promote(&mut self, sentences: &[Sentence]) -> Vec<Sentence> {
+        let wanted: HashSet<&Sentence> = sentences.iter().collect();
+        let mut counts: HashMap<Sentence, usize> = HashMap::new();
+        for (_, sentence) in self.retain(|sentence| !wanted.contains(sentence)) {
+            *counts.entry(sentence).or_default() += 1;
+        }
+        let mut seen = HashSet::new();
+        let newest = sentences
+            .iter()
+            .filter(|sentence| !sentence.is_empty() && seen.insert(*sentence))
+            .flat_map(|sentence| {
+                let n = counts.get(sentence).copied().unwrap_or(1);
+                std::iter::repeat_n(sentence.clone(), n)
+            })
+            .collect();
+        self.prepend(newest)
+    }
+
+    /// Inserts `copies` copies of each of `sentences` at the newest end of pool 0, which raises
+    /// their bigram weights.  The copies are interleaved so that all sentences are equally
+    /// recent.  The pools are then [rebalanced](History::rebalance), and the sentences that no
+    /// longer fit are returned.
+    pub fn boost(&mut self, sentences: &[Sentence], copies: usize) -> Vec<Sentence> {
+        let sentences: Vec<&Sentence> = sentences
+            .iter()
+            .filter(|sentence| !sentence.is_empty())
+            .collect();
+        let newest = (0..copies)
+            .flat_map(|_| sentences.iter().map(|&sentence| sentence.clone()))
+            .collect();
+        self.prepend(newest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::parse_phrases;
+    use crate::{
+        data::History,
+        test_util::{history, sentence},
+    };
+
+    fn sample() -> History {
+        history(vec![
+            vec!["你好", "飞书 文档"],
+            vec!["打开 飞书", "飞书 文档"],
+        ])
+    }
+
+    #[test]
+    fn phrases() {
+        assert_eq!(
+            parse_phrases("# jargon\n\n  飞书  文档 \n项目\n飞书 文档\n"),
+            vec![sentence("飞书 文档"), sentence("项目")]
+        );
+    }
+
+    #[test]
+    fn promote() {
+        let mut promoted = sample();
+        let dropped =
+            promoted.promote(&[sentence("项目"), sentence("飞书 文档"), sentence("项目")]);
+        assert!(dropped.is_empty());
+        assert_eq!(
+            promoted,
+            history(vec![
+                vec!["项目", "飞书 文档", "飞书 文档", "你好"],
+                vec!["打开 飞书"]
+            ])
+        );
+    }
+
+    #[test]
+    fn boost() {
+        let mut boosted = sample();
+        boosted.boost(&[sentence("项目"), sentence("飞书")], 2);
+        assert_eq!(
+            boosted.pools[0].0,
+            vec![
+                sentence("项目"),
+                sentence("飞书"),
+                sentence("项目"),
+                sentence("飞书"),
+                sentence("你好"),
+                sentence("飞书 文档"),
+            ]
+        );
+
+        let mut overflowing = sample();
+        overflowing.boost(&[sentence("项目")], 200);
+        assert_eq!(
+            overflowing
+                .pools
+                .iter()
+                .map(|pool| pool.0.len())
+                .collect::<Vec<_>>(),
+            vec![128, 76]
+        );
+        assert_eq!(overflowing.pools[1].0[72], sentence("你好"));
+    }
+}
@@ -0,0 +1,156 @@
+use regex::Regex;
+
+use crate::{
+    data::{History, Sentence, Word},
+    Result,
+};
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Exact(String),
+    Regex(Regex),
+}
+
+/// Rewrites words of sentences, see [`History::replace_words`].  The rewritten text is split at
+/// whitespaces, so a word can be replaced by a sequence of words, or removed by replacing it with
+/// an empty string.
+#[derive(Clone, Debug)]
+pub struct Replacement {
+    matcher: Matcher,
+    with: String,
+}
+
+impl Replacement {
+    /// Replaces words equal to `word` with `with`.
+    pub fn exact(word: &str, with: &str) -> Self {
+        Replacement {
+            matcher: Matcher::Exact(word.to_string()),
+            with: with.to_string(),
+        }
+    }
+
+    /// Replaces all matches of the regular expression `re` inside each word with `with`, where
+    /// `$1`, `$name`, etc. refer to the matched groups (see [`Regex::replace_all`]).
+    pub fn regex(re: &str, with: &str) -> Result<Self> {
+        Ok(Replacement {
+            matcher: Matcher::Regex(Regex::new(re)?),
+            with: with.to_string(),
+        })
+    }
+
+    /// Returns the words `word` is replaced with, or `None` if it does not match.
+    fn rewrite(&self, word: &Word) -> Option<Vec<Word>> {
+        let rewritten = match &self.matcher {
+            Matcher::Exact(exact) if *exact == word.0 => self.with.clone(),
+            Matcher::Regex(re) if re.is_match(&word.0) => {
+                re.replace_all(&word.0, self.with.as_str()).into_owned()
+            }
+            _ => return None,
+        };
+        Some(
+            rewritten
+                .split_whitespace()
+                .map(|word| Word(word.to_string()))
+                .collect(),
+        )
+    }
+
+    /// Returns `sentence` with its matching words rewritten, or `None` if that leaves it
+    /// unchanged.
+    pub fn apply(&self, sentence: &Sentence) -> Option<Sentence> {
+        let mut ret = Vec::with_capacity(sentence.0.len());
+        for word in &sentence.0 {
+            match self.rewrite(word) {
+                Some(mut words) => ret.append(&mut words),
+                None => ret.push(word.clone()),
+            }
+        }
+        (ret != sentence.0).then_some(Sentence(ret))
+    }
+}
+
+impl History {
+    /// Rewrites words of all sentences with `replacement`, and returns the number of sentences
+    /// that changed.  Sentences left without words are removed, the others stay where they are.
+    pub fn replace_words(&mut self, replacement: &Replacement) -> usize {
+        let mut affected = 0;
+        for pool in &mut self.pools {
+            for sentence in std::mem::take(&mut pool.0) {
+                match replacement.apply(&sentence) {
+                    Some(replaced) => {
+                        affected += 1;
+                        if !replaced.is_empty() {
+                            pool.0.push(replaced);
+                        }
+                    }
+                    None => pool.0.push(sentence),
+                }
+            }
+        }
+        affected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::Replacement;
+    use crate::{
+        data::History,
+        test_util::{history, sentence},
+        Result,
+    };
+
+    fn sample() -> History {
+        history(vec![vec!["飞书 文档", "你好"], vec!["打开 飞书"]])
+    }
+
+    #[test]
+    fn exact() {
+        let mut replaced = sample();
+        assert_eq!(
+            replaced.replace_words(&Replacement::exact("飞书", "Lark 办公")),
+            2
+        );
+        assert_eq!(
+            replaced,
+            history(vec![vec!["Lark 办公 文档", "你好"], vec!["打开 Lark 办公"]])
+        );
+        assert_eq!(replaced.replace_words(&Replacement::exact("飞", "x")), 0);
+        assert_eq!(
+            replaced.replace_words(&Replacement::exact("办公", " 办公 ")),
+            0
+        );
+    }
+
+    #[test]
+    fn regex() -> Result<()> {
+        let mut replaced = sample();
+        assert_eq!(
+            replaced.replace_words(&Replacement::regex("^(.)书$", "${1}机")?),
+            2
+        );
+        assert_eq!(
+            replaced.get_sentences(),
+            vec![
+                sentence("飞机 文档"),
+                sentence("你好"),
+                sentence("打开 飞机")
+            ]
+        );
+        assert_eq!(replaced.replace_words(&Replacement::regex("机", "机")?), 0);
+        assert!(Replacement::regex("(", "").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn removal() {
+        let mut replaced = sample();
+        assert_eq!(replaced.replace_words(&Replacement::exact("你好", "")), 1);
+        assert_eq!(
+            replaced,
+            history(vec![vec!["飞书 文档"], vec!["打开 飞书"]])
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use std::{fmt::Display, ops::Range};
+
+use regex::Regex;
+
+use crate::{
+    data::{History, Sentence, Word},
+    search::Location,
+    Result,
+};
+
+/// Character that replaces sensitive characters when masking them, see [`Redaction::Mask`].
+pub const MASK: char = '*';
+
+/// Finds sensitive data, e.g. phone numbers, inside sentences.
+///
+/// A sentence is scanned as the concatenation of its words, because the input method may have
+/// split what was typed into several words.  The built-in detector of password-like strings is an
+/// exception, it scans each word on its own so that e.g. "iPhone 15" is not taken for "iPhone15".
+#[derive(Clone, Debug)]
+pub struct Detector {
+    pub name: String,
+    regex: Regex,
+    /// Whether matches adjacent to ASCII letters or digits are discarded
+    bounded: bool,
+    /// Whether each word is scanned on its own instead of the concatenation of the words
+    per_word: bool,
+    /// Further checks a match has to pass
+    validate: fn(&str) -> bool,
+}
+
+impl Detector {
+    /// A detector named `name` that finds matches of the regular expression `re`.
+    pub fn new(name: &str, re: &str) -> Result<Self> {
+        Ok(Detector {
+            name: name.to_string(),
+            regex: Regex::new(re)?,
+            bounded: false,
+            per_word: false,
+            validate: |_| true,
+        })
+    }
+
+    /// Detectors for email addresses, mainland China mobile phone numbers and resident ID
+    /// numbers, bank card numbers, and password-like strings (at least 8 ASCII characters mixing
+    /// at least 3 of lowercase letters, uppercase letters, digits and symbols).
+    pub fn builtin() -> Vec<Self> {
+        let detector = |name: &str, re: &str, bounded, validate| Detector {
+            name: name.to_string(),
+            regex: Regex::new(re).expect("built-in patterns are valid regexes"),
+            bounded,
+            per_word: false,
+            validate,
+        };
+        vec![
+            detector(
+                "email",
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+                false,
+                |_| true,
+            ),
+            detector("phone", r"(?:\+?86[- ]?)?1[3-9][0-9]{9}", true, |_| true),
+            detector(
+                "id-number",
+                r"[1-9][0-9]{5}(?:18|19|20)[0-9]{2}(?:0[1-9]|1[0-2])(?:0[1-9]|[12][0-9]|3[01])[0-9]{3}[0-9Xx]",
+                true,
+                is_valid_id_number,
+            ),
+            detector("bank-card", r"[0-9]{16,19}", true, passes_luhn),
+            Detector {
+                per_word: true,
+                ..detector("password", r"[!-~]{8,}", false, is_password_like)
+            },
+        ]
+    }
+
+    /// Byte ranges of the matches inside `text`, the concatenation of words taking the byte
+    /// ranges `words`.
+    fn find(&self, text: &str, words: &[Range<usize>]) -> Vec<Range<usize>> {
+        if !self.per_word {
+            return self.find_in(text, 0..text.len());
+        }
+        words
+            .iter()
+            .flat_map(|word| self.find_in(text, word.clone()))
+            .collect()
+    }
+
+    /// Byte ranges of the matches inside `text[within]`, relative to `text`.
+    fn find_in(&self, text: &str, within: Range<usize>) -> Vec<Range<usize>> {
+        let is_bound = |c: Option<char>| !c.is_some_and(|c| c.is_ascii_alphanumeric());
+        let offset = within.start;
+        let haystack = &text[within];
+        self.regex
+            .find_iter(haystack)
+            .filter(|m| {
+                !self.bounded
+                    || (is_bound(haystack[..m.start()].chars().next_back())
+                        && is_bound(haystack[m.end()..].chars().next()))
+            })
+            .filter(|m| (self.validate)(m.as_str()))
+            .map(|m| offset + m.start()..offset + m.end())
+            .collect()
+    }
+}
+
+/// Checks the last character of an 18-digit resident ID number, an ISO 7064 MOD 11-2 check
+/// character.
+fn is_valid_id_number(id: &str) -> bool {
+    const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+    let sum: u32 = id
+        .chars()
+        .zip(WEIGHTS)
+        .map(|(c, w)| c.to_digit(10).unwrap_or_default() * w)
+        .sum();
+    let expected = b"10X98765432"[(sum % 11) as usize] as char;
+    id.chars()
+        .last()
+        .is_some_and(|c| c.to_ascii_uppercase() == expected)
+}
+
+/// Checks the Luhn checksum of a card number.
+fn passes_luhn(number: &str) -> bool {
+    let sum: u32 = number
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn is_password_like(s: &str) -> bool {
+    let classes: [fn(&char) -> bool; 4] = [
+        char::is_ascii_lowercase,
+        char::is_ascii_uppercase,
+        char::is_ascii_digit,
+        char::is_ascii_punctuation,
+    ];
+    classes
+        .iter()
+        .filter(|class| s.chars().any(|c| class(&c)))
+        .count()
+        >= 3
+}
+
+/// Sensitive data found by a [`Detector`], see [`History::scan`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub location: Location,
+    /// Name of the detector
+    pub detector: String,
+    /// The sensitive text
+    pub text: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: [{}] {}", self.location, self.detector, self.text)
+    }
+}
+
+/// What to do with sentences holding sensitive data, see [`History::redact`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Redaction {
+    /// Remove the sentences
+    Remove,
+    /// Replace each sensitive character with [`MASK`]
+    Mask,
+}
+
+/// Concatenates the words of `sentence`, returning the text and the byte range each word takes
+/// inside it.
+fn concat(sentence: &Sentence) -> (String, Vec<Range<usize>>) {
+    let mut text = String::new();
+    let mut ranges = Vec::with_capacity(sentence.0.len());
+    for word in &sentence.0 {
+        let start = text.len();
+        text.push_str(&word.0);
+        ranges.push(start..text.len());
+    }
+    (text, ranges)
+}
+
+/// Masks the characters of `sentence` covered by `hits`, byte ranges inside the concatenation
+/// of its words.
+fn mask(sentence: &Sentence, hits: &[Range<usize>]) -> Sentence {
+    let (_, ranges) = concat(sentence);
+    Sentence(
+        sentence
+            .0
+            .iter()
+            .zip(ranges)
+            .map(|(word, range)| {
+                Word(
+                    word.0
+                        .char_indices()
+                        .map(|(i, c)| {
+                            let pos = range.start + i;
+                            match hits.iter().any(|hit| hit.contains(&pos)) {
+                                true => MASK,
+                                false => c,
+                            }
+                        })
+                        .collect(),
+                )
+            })
+            .collect(),
+    )
+}
+
+impl History {
+    /// Looks for sensitive data with `detectors`, newest sentences first.
+    pub fn scan(&self, detectors: &[Detector]) -> Vec<Finding> {
+        let mut ret = Vec::new();
+        for (i, pool) in self.pools.iter().enumerate() {
+            for (j, sentence) in pool.0.iter().enumerate() {
+                let (text, words) = concat(sentence);
+                for detector in detectors {
+                    for hit in detector.find(&text, &words) {
+                        ret.push(Finding {
+                            location: Location { pool: i, index: j },
+                            detector: detector.name.clone(),
+                            text: text[hit].to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Removes or masks sentences holding sensitive data found by `detectors`, and returns the
+    /// number of affected sentences.  Other sentences stay where they are.
+    pub fn redact(&mut self, detectors: &[Detector], how: Redaction) -> usize {
+        let mut affected = 0;
+        for pool in &mut self.pools {
+            for sentence in std::mem::take(&mut pool.0) {
+                let (text, words) = concat(&sentence);
+                let hits: Vec<Range<usize>> = detectors
+                    .iter()
+                    .flat_map(|detector| detector.find(&text, &words))
+                    .collect();
+                match (hits.is_empty(), how) {
+                    (true, _) => pool.0.push(sentence),
+                    (false, Redaction::Remove) => affected += 1,
+                    (false, Redaction::Mask) => {
+                        affected += 1;
+                        pool.0.push(mask(&sentence, &hits));
+                    }
+                }
+            }
+        }
+        affected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{Detector, Redaction};
+    use crate::{test_util::history, Result};
+
+    fn found(content: &str) -> Vec<String> {
+        history(vec![vec![content]])
+            .scan(&Detector::builtin())
+            .into_iter()
+            .map(|finding| format!("{} {}", finding.detector, finding.text))
+            .collect()
+    }
+
+    #[test]
+    fn builtin() {
+        assert_eq!(
+            found("我的 电话 是 138 1234 5678"),
+            vec!["phone 13812345678"]
+        );
+        assert!(found("订单号 0138123456789").is_empty());
+        assert_eq!(
+            found("邮箱 someone.x@example.com 谢谢"),
+            vec!["email someone.x@example.com"]
+        );
+        assert_eq!(
+            found("身份证 11010519491231002X"),
+            vec!["id-number 11010519491231002X"]
+        );
+        assert!(found("身份证 110105194912310021").is_empty());
+        assert_eq!(
+            found("卡号 4111111111111111"),
+            vec!["bank-card 4111111111111111"]
+        );
+        assert!(found("卡号 4111111111111112").is_empty());
+        assert_eq!(found("密码 Tr0ub4dor&3"), vec!["password Tr0ub4dor&3"]);
+        assert_eq!(
+            found("iPhone 15 密码 Tr0ub4dor&3"),
+            vec!["password Tr0ub4dor&3"]
+        );
+        assert!(found("买 iPhone 15 Pro").is_empty());
+        assert!(found("你好 hello world").is_empty());
+    }
+
+    #[test]
+    fn custom() -> Result<()> {
+        let history = history(vec![vec!["项目 代号 ALPHA7"]]);
+        let findings = history.scan(&[Detector::new("codename", "ALPHA[0-9]")?]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].to_string(), "0:0: [codename] ALPHA7");
+        assert!(Detector::new("broken", "(").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn redact() {
+        let original = history(vec![
+            vec!["打 138 1234 5678 找 我", "你好"],
+            vec!["someone@example.com"],
+        ]);
+
+        let mut masked = original.clone();
+        assert_eq!(masked.redact(&Detector::builtin(), Redaction::Mask), 2);
+        assert_eq!(
+            masked,
+            history(vec![
+                vec!["打 *** **** **** 找 我", "你好"],
+                vec!["*******************"]
+            ])
+        );
+
+        let mut removed = original;
+        assert_eq!(removed.redact(&Detector::builtin(), Redaction::Remove), 2);
+        assert_eq!(removed, history(vec![vec!["你好"], vec![]]));
+    }
+}
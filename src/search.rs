@@ -0,0 +1,184 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+
+use crate::{
+    data::{History, Sentence},
+    Error, Result,
+};
+
+/// What a [`Pattern`] is matched against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scope {
+    /// Each word of a sentence on its own
+    Word,
+    /// The whole sentence, rendered with its words separated by spaces
+    Sentence,
+}
+
+/// A literal string or a regular expression to look for in sentences.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    regex: Regex,
+    scope: Scope,
+}
+
+impl Pattern {
+    /// Matches sentences containing `text`.
+    pub fn literal(text: &str, scope: Scope) -> Self {
+        Pattern {
+            regex: Regex::new(&regex::escape(text)).expect("escaped text is a valid regex"),
+            scope,
+        }
+    }
+
+    /// Matches sentences containing a match of the regular expression `re`.
+    pub fn regex(re: &str, scope: Scope) -> Result<Self> {
+        Ok(Pattern {
+            regex: Regex::new(re)?,
+            scope,
+        })
+    }
+
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Checks if `sentence` contains a match of this pattern.
+    pub fn is_match(&self, sentence: &Sentence) -> bool {
+        match self.scope {
+            Scope::Word => sentence
+                .0
+                .iter()
+                .any(|word| !word.is_empty() && self.regex.is_match(&word.0)),
+            Scope::Sentence => self.regex.is_match(&sentence.to_string()),
+        }
+    }
+}
+
+/// Where a sentence lives inside a [`History`].
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    /// Index of the pool holding the sentence
+    pub pool: usize,
+    /// Index of the sentence inside the pool, 0 being the newest
+    pub index: usize,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.pool, self.index)
+    }
+}
+
+impl FromStr for Location {
+    type Err = Error;
+
+    /// Parses a location written as "<pool>:<index>", e.g. "1:20".
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            || Error::Message(format!("Invalid location '{}', expected <pool>:<index>", s));
+        let (pool, index) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(Location {
+            pool: pool.trim().parse().map_err(|_| invalid())?,
+            index: index.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl History {
+    /// Returns the sentence at `location`, if any.
+    pub fn get(&self, location: Location) -> Option<&Sentence> {
+        self.pools
+            .get(location.pool)
+            .and_then(|pool| pool.0.get(location.index))
+    }
+
+    /// Finds the sentences matching `pattern`, newest first.
+    pub fn search(&self, pattern: &Pattern) -> Vec<(Location, &Sentence)> {
+        self.pools
+            .iter()
+            .enumerate()
+            .flat_map(|(i, pool)| {
+                pool.0
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, sentence)| (Location { pool: i, index: j }, sentence))
+            })
+            .filter(|(_, sentence)| pattern.is_match(sentence))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{Location, Pattern, Scope};
+    use crate::{
+        data::History,
+        test_util::{history, sentence},
+        Result,
+    };
+
+    fn sample() -> History {
+        history(vec![vec!["音乐 好听", "你好"], vec!["好 音乐", "a.b"]])
+    }
+
+    fn locations(history: &History, pattern: &Pattern) -> Vec<String> {
+        history
+            .search(pattern)
+            .into_iter()
+            .map(|(location, _)| location.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn literal() {
+        let history = sample();
+        assert_eq!(
+            locations(&history, &Pattern::literal("好", Scope::Word)),
+            vec!["0:0", "0:1", "1:0"]
+        );
+        assert_eq!(
+            locations(&history, &Pattern::literal("乐 好", Scope::Word)),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            locations(&history, &Pattern::literal("乐 好", Scope::Sentence)),
+            vec!["0:0"]
+        );
+        assert_eq!(
+            locations(&history, &Pattern::literal(".", Scope::Word)),
+            vec!["1:1"]
+        );
+    }
+
+    #[test]
+    fn regex() -> Result<()> {
+        let history = sample();
+        assert_eq!(
+            locations(&history, &Pattern::regex("^好", Scope::Word)?),
+            vec!["0:0", "1:0"]
+        );
+        assert_eq!(
+            locations(&history, &Pattern::regex("^好", Scope::Sentence)?),
+            vec!["1:0"]
+        );
+        assert!(Pattern::regex("(", Scope::Word).is_err());
+        assert_eq!(
+            history.get(Location { pool: 1, index: 1 }),
+            Some(&sentence("a.b"))
+        );
+        assert_eq!(history.get(Location { pool: 2, index: 0 }), None);
+        Ok(())
+    }
+
+    #[test]
+    fn location() -> Result<()> {
+        assert_eq!("1:20".parse::<Location>()?, Location { pool: 1, index: 20 });
+        assert!("1".parse::<Location>().is_err());
+        assert!("1:x".parse::<Location>().is_err());
+        Ok(())
+    }
+}
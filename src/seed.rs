@@ -0,0 +1,177 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    data::{History, Pool, Sentence, Word},
+    merging::POOL_SIZE,
+    utils::split_vec,
+    Error, Result,
+};
+
+/// Words that [`seed`] segments sentences into.
+#[derive(Clone, Debug, Default)]
+pub struct Lexicon {
+    words: HashSet<String>,
+    /// Length of the longest word, in characters
+    longest: usize,
+}
+
+impl Lexicon {
+    /// Parses a word list, one word per line.  Only the first field of a line counts, so that
+    /// dictionaries with e.g. pinyin or frequencies after each word can be used as they are.
+    /// Empty lines and lines starting with "#" are ignored.
+    pub fn parse(content: &str) -> Self {
+        let mut ret = Lexicon::default();
+        for word in content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_whitespace().next())
+        {
+            ret.longest = ret.longest.max(word.chars().count());
+            ret.words.insert(word.to_string());
+        }
+        ret
+    }
+
+    pub fn load<P>(p: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let content = std::fs::read_to_string(&p).map_err(|e| {
+            Error::IoError(format!(
+                "Could not load lexicon from path '{}': {}",
+                p.as_ref().display(),
+                e,
+            ))
+        })?;
+        Ok(Lexicon::parse(&content))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Segments `text` into words by forward maximum matching: at each position, the longest
+    /// word of the lexicon is taken.  Where no word matches, a run of ASCII letters and digits
+    /// makes up one word, any other character a word on its own.  Whitespaces separate words.
+    pub fn segment(&self, text: &str) -> Vec<Word> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut ret = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+            let longest = (2..=self.longest.min(chars.len() - i)).rev().find(|&n| {
+                self.words
+                    .contains(&chars[i..i + n].iter().collect::<String>())
+            });
+            let n = match longest {
+                Some(n) => n,
+                None if chars[i].is_ascii_alphanumeric() => chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric())
+                    .count(),
+                None => 1,
+            };
+            ret.push(Word(chars[i..i + n].iter().collect()));
+            i += n;
+        }
+        ret
+    }
+}
+
+/// Splits `corpus` into sentences at line breaks and at every character that is neither a letter,
+/// a digit nor a whitespace, e.g. punctuation marks.
+fn split_sentences(corpus: &str) -> impl Iterator<Item = &str> {
+    corpus
+        .split(|c: char| c == '\n' || !(c.is_alphanumeric() || c.is_whitespace()))
+        .map(str::trim)
+        .filter(|sentence| !sentence.is_empty())
+}
+
+/// Generates a history from the plain text `corpus`, e.g. documentation, as if its sentences had
+/// been typed in order: the corpus is split into sentences at line breaks and punctuation marks,
+/// each sentence is [segmented](Lexicon::segment) with `lexicon`, and the pools are filled up to
+/// [`libime`][libime]'s capacities with the sentences from the end of the corpus, which count as
+/// the newest.
+///
+/// [libime]: https://github.com/fcitx/libime
+pub fn seed(corpus: &str, lexicon: &Lexicon) -> History {
+    let mut sentences: Vec<Sentence> = split_sentences(corpus)
+        .map(|sentence| Sentence(lexicon.segment(sentence)))
+        .collect();
+    sentences.reverse();
+    sentences.truncate(POOL_SIZE.iter().sum());
+    History::new(
+        split_vec(sentences, POOL_SIZE)
+            .into_iter()
+            .map(Pool)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{seed, Lexicon};
+    use crate::{data::Sentence, test_util::sentence};
+
+    fn lexicon() -> Lexicon {
+        Lexicon::parse("# words\n研究 yan'jiu 100\n研究生\n生命\n命\n起源\n\n文档\n")
+    }
+
+    #[test]
+    fn segment() {
+        let lexicon = lexicon();
+        assert_eq!(lexicon.len(), 6);
+        assert_eq!(
+            Sentence(lexicon.segment("研究生命起源")),
+            sentence("研究生 命 起源")
+        );
+        assert_eq!(
+            Sentence(lexicon.segment("查看 API2 文档x")),
+            sentence("查 看 API2 文档 x")
+        );
+        assert!(Lexicon::default().segment(" ").is_empty());
+    }
+
+    #[test]
+    fn fill() {
+        let history = seed("研究生命起源。\n查看文档，\n\n文档！", &lexicon());
+        assert_eq!(
+            history
+                .pools
+                .iter()
+                .map(|pool| pool.0.len())
+                .collect::<Vec<_>>(),
+            vec![3, 0, 0]
+        );
+        assert_eq!(
+            history.get_sentences(),
+            vec![
+                sentence("文档"),
+                sentence("查 看 文档"),
+                sentence("研究生 命 起源"),
+            ]
+        );
+
+        let corpus = "文档。".repeat(70000) + "研究。";
+        let history = seed(&corpus, &lexicon());
+        assert_eq!(
+            history
+                .pools
+                .iter()
+                .map(|pool| pool.0.len())
+                .collect::<Vec<_>>(),
+            vec![128, 8192, 61681]
+        );
+        assert_eq!(history.pools[0].0[0], sentence("研究"));
+    }
+}
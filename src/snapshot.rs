@@ -0,0 +1,277 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    data::History,
+    persist,
+    utils::{timestamp, xdg_dir},
+    Error, Result,
+};
+
+/// Number of snapshots kept for each source path, older ones are removed when a new snapshot is
+/// taken.
+pub const DEFAULT_KEEP: usize = 10;
+
+/// A copy of a history file taken right before it was overwritten.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// Identifies the snapshot inside its store, it is the time in UTC when the snapshot was
+    /// taken, formatted as `YYYYmmddTHHMMSSZ`, possibly followed by a `-N` suffix to keep it
+    /// unique.
+    pub id: String,
+    /// Path of the history file this snapshot was taken from.
+    pub source: PathBuf,
+    /// Path of the snapshot file inside the store.
+    pub path: PathBuf,
+}
+
+impl Snapshot {
+    /// Loads the history saved in this snapshot.
+    pub fn load(&self) -> Result<History> {
+        History::load(&self.path)
+    }
+
+    /// Writes the history saved in this snapshot to `target`, or back to its source path if
+    /// `target` is `None`.  Returns the path that was written.
+    pub fn restore(&self, target: Option<&Path>) -> Result<PathBuf> {
+        let target = target.unwrap_or(&self.source).to_path_buf();
+        self.load()?.save(&target)?;
+        Ok(target)
+    }
+}
+
+/// A directory of [`Snapshot`]s, keyed by timestamp and source path.  Snapshot files are named
+/// `<id>_<escaped source path>.history`.
+#[derive(Clone, Debug)]
+pub struct SnapshotStore {
+    root: PathBuf,
+    keep: usize,
+}
+
+impl SnapshotStore {
+    /// Creates a store at `root` that keeps at most `keep` snapshots for each source path.  The
+    /// directory is created when the first snapshot is taken.
+    pub fn new<P>(root: P, keep: usize) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        SnapshotStore {
+            root: root.as_ref().to_path_buf(),
+            keep,
+        }
+    }
+
+    /// Opens the store at `$XDG_STATE_HOME/libime-history-merge/snapshots`, where
+    /// `$XDG_STATE_HOME` defaults to `~/.local/state`.
+    pub fn open_default() -> Result<Self> {
+        Ok(SnapshotStore::new(
+            xdg_dir("XDG_STATE_HOME", ".local/state")?
+                .join("libime-history-merge")
+                .join("snapshots"),
+            DEFAULT_KEEP,
+        ))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Copies the current content of `source` into the store, then removes the oldest snapshots
+    /// of `source` beyond the store's limit.
+    pub fn take<P>(&self, source: P) -> Result<Snapshot>
+    where
+        P: AsRef<Path>,
+    {
+        let source = fs::canonicalize(source.as_ref())?;
+        fs::create_dir_all(&self.root)?;
+
+        // Count up from the newest snapshot of the same second, rather than filling gaps left by
+        // rotation, so that a new snapshot always sorts as the newest
+        let stamp = timestamp();
+        let id = match self
+            .list()?
+            .iter()
+            .filter(|snapshot| order_key(&snapshot.id).0 == stamp)
+            .map(|snapshot| order_key(&snapshot.id).1)
+            .max()
+        {
+            Some(n) => format!("{}-{}", stamp, n + 1),
+            None => stamp,
+        };
+        let path = self
+            .root
+            .join(format!("{}_{}.history", id, escape(&source)));
+        persist::write_atomic(&path, &fs::read(&source)?)?;
+
+        for stale in self.list_for(&source)?.iter().skip(self.keep) {
+            log::debug!("removing stale snapshot '{}'", stale.path.display());
+            fs::remove_file(&stale.path)?;
+        }
+
+        Ok(Snapshot { id, source, path })
+    }
+
+    /// Lists all snapshots in this store, newest first.
+    pub fn list(&self) -> Result<Vec<Snapshot>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut ret = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let (id, source) = match name
+                .strip_suffix(".history")
+                .and_then(|stem| stem.split_once('_'))
+            {
+                Some((id, source)) => (id.to_string(), unescape(source)),
+                None => continue,
+            };
+            ret.push(Snapshot { id, source, path });
+        }
+        ret.sort_by(|lhs, rhs| order_key(&rhs.id).cmp(&order_key(&lhs.id)));
+        Ok(ret)
+    }
+
+    /// Lists snapshots taken from `source`, newest first.
+    pub fn list_for<P>(&self, source: P) -> Result<Vec<Snapshot>>
+    where
+        P: AsRef<Path>,
+    {
+        let source =
+            fs::canonicalize(source.as_ref()).unwrap_or_else(|_| source.as_ref().to_path_buf());
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|snapshot| snapshot.source == source)
+            .collect())
+    }
+
+    /// Finds the snapshot with the given `id`, a unique prefix of an id is also accepted.
+    pub fn get(&self, id: &str) -> Result<Snapshot> {
+        let snapshots = self.list()?;
+        if let Some(snapshot) = snapshots.iter().find(|snapshot| snapshot.id == id) {
+            return Ok(snapshot.clone());
+        }
+        let mut candidates = snapshots
+            .into_iter()
+            .filter(|snapshot| snapshot.id.starts_with(id));
+        match (candidates.next(), candidates.next()) {
+            (Some(snapshot), None) => Ok(snapshot),
+            (None, _) => Err(Error::LogicError(format!("No snapshot matches '{}'", id))),
+            (Some(_), Some(_)) => Err(Error::LogicError(format!(
+                "More than one snapshot matches '{}'",
+                id
+            ))),
+        }
+    }
+}
+
+/// Splits a snapshot id into its timestamp and its `-N` suffix (0 if there is none), so that ids
+/// sort by time first and then by the suffix as a number, e.g. `…-9` before `…-10`.
+fn order_key(id: &str) -> (&str, u64) {
+    match id.rsplit_once('-') {
+        Some((stamp, n)) => match n.parse() {
+            Ok(n) => (stamp, n),
+            Err(_) => (id, 0),
+        },
+        None => (id, 0),
+    }
+}
+
+/// Encodes `path` so that it can be embedded in a file name.
+fn escape(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('%', "%25")
+        .replace('/', "%2F")
+}
+
+/// Reverses [`escape`].
+fn unescape(name: &str) -> PathBuf {
+    PathBuf::from(name.replace("%2F", "/").replace("%25", "%"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+
+    use super::{order_key, SnapshotStore};
+    use crate::{data::History, test_util::history, Result};
+
+    #[test]
+    fn take_and_restore() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = SnapshotStore::new(dir.path().join("snapshots"), 10);
+        let source = dir.path().join("100%/user.history");
+        fs::create_dir(source.parent().unwrap())?;
+
+        history(vec![vec!["音乐"]]).save(&source)?;
+        let snapshot = store.take(&source)?;
+        history(vec![vec!["好听"]]).save(&source)?;
+
+        assert_eq!(store.list()?, vec![snapshot.clone()]);
+        assert_eq!(snapshot.source, fs::canonicalize(&source)?);
+        assert_eq!(store.get(&snapshot.id[..8])?, snapshot);
+
+        snapshot.restore(None)?;
+        assert_eq!(History::load(&source)?, history(vec![vec!["音乐"]]));
+        Ok(())
+    }
+
+    #[test]
+    fn rotation() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = SnapshotStore::new(dir.path().join("snapshots"), 2);
+        let source = dir.path().join("user.history");
+        let other = dir.path().join("other.history");
+        history(vec![vec!["音乐"]]).save(&source)?;
+        history(vec![vec!["音乐"]]).save(&other)?;
+
+        let other_snapshot = store.take(&other)?;
+        let ids: Vec<String> = (0..3)
+            .map(|_| store.take(&source).map(|snapshot| snapshot.id))
+            .collect::<Result<_>>()?;
+
+        let kept: Vec<String> = store
+            .list_for(&source)?
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        assert_eq!(kept, vec![ids[2].clone(), ids[1].clone()]);
+        assert_eq!(store.list_for(&other)?, vec![other_snapshot]);
+        Ok(())
+    }
+
+    #[test]
+    fn rotation_within_a_second() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = SnapshotStore::new(dir.path().join("snapshots"), 3);
+        let source = dir.path().join("user.history");
+        history(vec![vec!["音乐"]]).save(&source)?;
+
+        let ids: Vec<String> = (0..12)
+            .map(|_| store.take(&source).map(|snapshot| snapshot.id))
+            .collect::<Result<_>>()?;
+        let kept: Vec<String> = store
+            .list_for(&source)?
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        assert_eq!(kept, vec![ids[11].clone(), ids[10].clone(), ids[9].clone()]);
+        Ok(())
+    }
+
+    #[test]
+    fn ids_sort_by_counter_numerically() {
+        assert!(order_key("20260101T000000Z-10") > order_key("20260101T000000Z-9"));
+        assert!(order_key("20260101T000000Z-1") > order_key("20260101T000000Z"));
+        assert!(order_key("20260101T000001Z") > order_key("20260101T000000Z-10"));
+    }
+}
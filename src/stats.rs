@@ -0,0 +1,220 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::Serialize;
+
+use crate::data::{History, Sentence, Word};
+
+/// Number of sentences and words inside a [`Pool`](crate::data::Pool).
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PoolStats {
+    pub sentences: usize,
+    pub words: usize,
+}
+
+/// How often a word occurs in a history.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WordCount {
+    pub word: String,
+    pub count: usize,
+}
+
+/// How often a word directly follows another word inside the sentences of a history.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BigramCount {
+    pub prev: String,
+    pub cur: String,
+    pub count: usize,
+}
+
+/// Summary of what a history holds, see [`History::stats`].  Empty words are not counted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Stats {
+    pub pools: Vec<PoolStats>,
+    pub sentences: usize,
+    pub words: usize,
+    /// Number of distinct words
+    pub vocabulary: usize,
+    pub avg_sentence_words: f64,
+    pub avg_sentence_chars: f64,
+    /// Most frequent words, most frequent first
+    pub top_words: Vec<WordCount>,
+    /// Most frequent pairs of adjacent words, most frequent first
+    pub top_bigrams: Vec<BigramCount>,
+}
+
+/// Returns the non-empty words of `sentence`.
+fn words(sentence: &Sentence) -> impl Iterator<Item = &Word> {
+    sentence.0.iter().filter(|word| !word.is_empty())
+}
+
+/// Sorts `counts` descending by count, breaking ties by key, and keeps the first `n` of them.
+fn most_frequent<K: Ord>(counts: HashMap<K, usize>, n: usize) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|lhs, rhs| rhs.1.cmp(&lhs.1).then_with(|| lhs.0.cmp(&rhs.0)));
+    counts.truncate(n);
+    counts
+}
+
+impl History {
+    /// Counts sentences and words of this history, reporting the `top` most frequent words and
+    /// pairs of adjacent words.
+    pub fn stats(&self, top: usize) -> Stats {
+        let pools: Vec<PoolStats> = self
+            .pools
+            .iter()
+            .map(|pool| PoolStats {
+                sentences: pool.0.len(),
+                words: pool.0.iter().map(|sentence| words(sentence).count()).sum(),
+            })
+            .collect();
+        let sentences: usize = pools.iter().map(|pool| pool.sentences).sum();
+        let words_count: usize = pools.iter().map(|pool| pool.words).sum();
+
+        let mut unigrams: HashMap<&str, usize> = HashMap::new();
+        let mut bigrams: HashMap<(&str, &str), usize> = HashMap::new();
+        let mut chars = 0;
+        for sentence in self.pools.iter().flat_map(|pool| &pool.0) {
+            let sentence: Vec<&str> = words(sentence).map(|word| word.0.as_str()).collect();
+            for word in &sentence {
+                *unigrams.entry(word).or_default() += 1;
+                chars += word.chars().count();
+            }
+            for pair in sentence.windows(2) {
+                *bigrams.entry((pair[0], pair[1])).or_default() += 1;
+            }
+        }
+        let vocabulary = unigrams.len();
+        let average = |total: usize| match sentences {
+            0 => 0.0,
+            _ => total as f64 / sentences as f64,
+        };
+
+        Stats {
+            pools,
+            sentences,
+            words: words_count,
+            vocabulary,
+            avg_sentence_words: average(words_count),
+            avg_sentence_chars: average(chars),
+            top_words: most_frequent(unigrams, top)
+                .into_iter()
+                .map(|(word, count)| WordCount {
+                    word: word.to_string(),
+                    count,
+                })
+                .collect(),
+            top_bigrams: most_frequent(bigrams, top)
+                .into_iter()
+                .map(|((prev, cur), count)| BigramCount {
+                    prev: prev.to_string(),
+                    cur: cur.to_string(),
+                    count,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:>6} {:>10} {:>10}", "pool", "sentences", "words")?;
+        for (i, pool) in self.pools.iter().enumerate() {
+            writeln!(f, "{:>6} {:>10} {:>10}", i, pool.sentences, pool.words)?;
+        }
+        writeln!(
+            f,
+            "{:>6} {:>10} {:>10}",
+            "total", self.sentences, self.words
+        )?;
+        writeln!(f)?;
+        writeln!(f, "vocabulary size: {}", self.vocabulary)?;
+        writeln!(
+            f,
+            "average sentence length: {:.2} words, {:.2} characters",
+            self.avg_sentence_words, self.avg_sentence_chars,
+        )?;
+        if !self.top_words.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{:>10}  top words", "count")?;
+            for wc in &self.top_words {
+                writeln!(f, "{:>10}  {}", wc.count, wc.word)?;
+            }
+        }
+        if !self.top_bigrams.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{:>10}  top bigrams", "count")?;
+            for bc in &self.top_bigrams {
+                writeln!(f, "{:>10}  {} {}", bc.count, bc.prev, bc.cur)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{BigramCount, PoolStats, Stats, WordCount};
+    use crate::{data::History, test_util::history};
+
+    #[test]
+    fn counts() {
+        let history = history(vec![
+            vec!["音乐 好听", "音乐 好听 🎵"],
+            vec!["你好"],
+            vec![],
+        ]);
+        assert_eq!(
+            history.stats(2),
+            Stats {
+                pools: vec![
+                    PoolStats {
+                        sentences: 2,
+                        words: 5,
+                    },
+                    PoolStats {
+                        sentences: 1,
+                        words: 1,
+                    },
+                    PoolStats::default(),
+                ],
+                sentences: 3,
+                words: 6,
+                vocabulary: 4,
+                avg_sentence_words: 2.0,
+                avg_sentence_chars: 11.0 / 3.0,
+                top_words: vec![
+                    WordCount {
+                        word: "好听".to_string(),
+                        count: 2,
+                    },
+                    WordCount {
+                        word: "音乐".to_string(),
+                        count: 2,
+                    },
+                ],
+                top_bigrams: vec![
+                    BigramCount {
+                        prev: "音乐".to_string(),
+                        cur: "好听".to_string(),
+                        count: 2,
+                    },
+                    BigramCount {
+                        prev: "好听".to_string(),
+                        cur: "🎵".to_string(),
+                        count: 1,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let stats = History::default().stats(10);
+        assert_eq!(stats.sentences, 0);
+        assert_eq!(stats.avg_sentence_words, 0.0);
+        assert!(stats.top_words.is_empty());
+    }
+}
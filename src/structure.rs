@@ -0,0 +1,438 @@
+use std::{borrow::Cow, fmt::Display};
+
+use crate::{
+    data_bytes::{decompress_pools, FORMAT_VERSION_V2, FORMAT_VERSION_V3, MAGIC},
+    walk::{self, Source},
+    wrapper::Wrapper,
+    BytesDeserializer,
+};
+
+/// Number of bytes shown per line of a hex dump.
+const BYTES_PER_LINE: usize = 8;
+/// Fields longer than this many lines of hex dump are cut short when displayed.
+const MAX_LINES: usize = 4;
+
+/// Part of a binary history a [`Field`] belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Section {
+    /// Magic and format version
+    Header,
+    /// Pools of a version 2 history
+    Pools,
+    /// Compressed pools of a version 3 history
+    Compressed,
+    /// Pools of a version 3 history after decompression
+    Decompressed,
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Section::Header => "header",
+            Section::Pools => "pools",
+            Section::Compressed => "compressed pools",
+            Section::Decompressed => {
+                "decompressed pools (offsets are relative to the decompressed data)"
+            }
+        })
+    }
+}
+
+/// A field of a binary history, as read by [`dump_structure`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub section: Section,
+    /// Offset of the field inside the file, or inside the decompressed data for
+    /// [`Section::Decompressed`]
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+    /// What the field is, e.g. "word 0:1:2", where pools, sentences and words are numbered in the
+    /// order they are stored (sentences oldest first)
+    pub name: String,
+    /// The decoded value
+    pub value: String,
+}
+
+/// The fields of a binary history, in the order they are stored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Structure {
+    /// The general-purpose compression the history was unwrapped from, if any
+    pub wrapper: Option<Wrapper>,
+    pub fields: Vec<Field>,
+    /// Why reading stopped before the end of the data, if it did
+    pub error: Option<String>,
+}
+
+impl Display for Structure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(wrapper) = self.wrapper {
+            writeln!(
+                f,
+                "# unwrapped from {}, offsets are relative to the unwrapped data",
+                wrapper
+            )?;
+        }
+        let mut section = None;
+        for field in &self.fields {
+            if section != Some(field.section) {
+                section = Some(field.section);
+                writeln!(f, "# {}", field.section)?;
+            }
+            let lines: Vec<&[u8]> = field.bytes.chunks(BYTES_PER_LINE).collect();
+            for (i, line) in lines.iter().enumerate().take(MAX_LINES) {
+                let hex = line
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let offset = field.offset + i * BYTES_PER_LINE;
+                match i {
+                    0 => writeln!(
+                        f,
+                        "{:08x}  {:<23}  {:<18}  {}",
+                        offset, hex, field.name, field.value
+                    )?,
+                    _ => writeln!(f, "{:08x}  {}", offset, hex)?,
+                }
+            }
+            if lines.len() > MAX_LINES {
+                writeln!(
+                    f,
+                    "{:>8}  ... {} more byte(s)",
+                    "",
+                    field.bytes.len() - MAX_LINES * BYTES_PER_LINE
+                )?;
+            }
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "# error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads fields from a part of a binary history.
+struct Reader<'a> {
+    de: BytesDeserializer<'a>,
+    len: usize,
+    /// Offset of the part being read
+    base: usize,
+    section: Section,
+    fields: Vec<Field>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], base: usize, section: Section) -> Self {
+        Reader {
+            de: BytesDeserializer::new(data),
+            len: data.len(),
+            base,
+            section,
+            fields: Vec::new(),
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.base + self.len - self.de.remaining_len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<Vec<u8>> {
+        self.de.next_exact_bytes(len).ok().map(<[u8]>::to_vec)
+    }
+
+    fn push(&mut self, offset: usize, bytes: Vec<u8>, name: String, value: String) {
+        self.fields.push(Field {
+            section: self.section,
+            offset,
+            bytes,
+            name,
+            value,
+        });
+    }
+
+    /// Reads a big-endian u32 named `name`, described by `value`.
+    fn u32<F>(&mut self, name: String, value: F) -> Result<u32, String>
+    where
+        F: FnOnce(u32) -> String,
+    {
+        let offset = self.offset();
+        let left = self.de.remaining_len();
+        let bytes = self.bytes(4).ok_or_else(|| {
+            format!(
+                "data ends at offset 0x{:x} inside {} (4 byte(s) needed, {} left)",
+                offset, name, left
+            )
+        })?;
+        let n = u32::from_be_bytes(bytes[..].try_into().expect("4 bytes are read"));
+        self.push(offset, bytes, name, value(n));
+        Ok(n)
+    }
+}
+
+impl Source for Reader<'_> {
+    fn offset(&self) -> u64 {
+        Reader::offset(self) as u64
+    }
+
+    fn u32(&mut self, field: walk::Field) -> std::io::Result<Option<u32>> {
+        let describe = |n| match field {
+            walk::Field::Pool(_) => format!("{} sentence(s)", n),
+            walk::Field::Sentence(..) => format!("{} word(s)", n),
+            _ => format!("{} byte(s)", n),
+        };
+        Ok(Reader::u32(self, field.to_string(), describe).ok())
+    }
+
+    fn bytes(&mut self, field: walk::Field, len: u32) -> std::io::Result<bool> {
+        let offset = Reader::offset(self);
+        let Some(bytes) = Reader::bytes(self, len as usize) else {
+            return Ok(false);
+        };
+        let value = match std::str::from_utf8(&bytes) {
+            Ok(word) => format!("{:?}", word),
+            Err(_) => "invalid UTF-8".to_string(),
+        };
+        self.push(offset, bytes, field.to_string(), value);
+        Ok(true)
+    }
+
+    fn rest(&mut self) -> std::io::Result<u64> {
+        let offset = Reader::offset(self);
+        let left = self.de.remaining_len();
+        if left > 0 {
+            let bytes = Reader::bytes(self, left).expect("the bytes left are read");
+            let value = format!("{} byte(s) after the last pool", left);
+            self.push(offset, bytes, "trailing data".to_string(), value);
+        }
+        Ok(left as u64)
+    }
+}
+
+/// Reads the fields of the binary history `content` one by one, with their offsets and decoded
+/// values, stopping at the first field that cannot be read.  The compressed and decompressed
+/// pools of a version 3 history are reported as separate [`Section`]s.  A file wrapped in
+/// general-purpose compression (see [`Wrapper`]) is unwrapped first.
+pub fn dump_structure(content: &[u8]) -> Structure {
+    let wrapper = Wrapper::detect(content);
+    let unwrapped = match wrapper.map(|wrapper| wrapper.unwrap(content)) {
+        Some(Ok(unwrapped)) => Cow::Owned(unwrapped),
+        Some(Err(e)) => {
+            return Structure {
+                wrapper,
+                error: Some(format!("unwrapping failed: {}", e)),
+                ..Structure::default()
+            }
+        }
+        None => Cow::Borrowed(content),
+    };
+    let content = &unwrapped[..];
+    let mut header = Reader::new(content, 0, Section::Header);
+    let header_result = header
+        .u32("magic".to_string(), |n| match n {
+            MAGIC => format!("0x{:08x}", n),
+            _ => format!("0x{:08x} (expected 0x{:08x})", n, MAGIC),
+        })
+        .and_then(|magic| match magic {
+            MAGIC => header.u32("version".to_string(), |n| match n {
+                FORMAT_VERSION_V2 => n.to_string(),
+                FORMAT_VERSION_V3 => format!("{} (pools are zstd compressed)", n),
+                _ => format!("{} (unsupported)", n),
+            }),
+            _ => Err("invalid magic, not a binary history".to_string()),
+        });
+    let mut ret = Structure {
+        wrapper,
+        fields: std::mem::take(&mut header.fields),
+        error: None,
+    };
+    let version = match header_result {
+        Ok(version) => version,
+        Err(e) => {
+            ret.error = Some(e);
+            return ret;
+        }
+    };
+
+    let body_offset = header.offset();
+    let body = &content[body_offset..];
+    let decompressed;
+    let mut reader = match version {
+        FORMAT_VERSION_V2 => Reader::new(body, body_offset, Section::Pools),
+        FORMAT_VERSION_V3 => {
+            let mut compressed = Field {
+                section: Section::Compressed,
+                offset: body_offset,
+                bytes: body.to_vec(),
+                name: "compressed".to_string(),
+                value: format!("{} byte(s)", body.len()),
+            };
+            match decompress_pools(body) {
+                Ok(data) => {
+                    compressed.value += &format!(", {} decompressed", data.len());
+                    ret.fields.push(compressed);
+                    decompressed = data;
+                    Reader::new(&decompressed, 0, Section::Decompressed)
+                }
+                Err(e) => {
+                    ret.fields.push(compressed);
+                    ret.error = Some(format!("zstd decompression failed: {}", e));
+                    return ret;
+                }
+            }
+        }
+        _ => {
+            ret.error = Some(format!(
+                "unsupported format version, expected {} or {}",
+                FORMAT_VERSION_V2, FORMAT_VERSION_V3
+            ));
+            return ret;
+        }
+    };
+    let walked = walk::walk(&mut reader).expect("reading from memory does not fail");
+    ret.error = walked
+        .end
+        .map(|end| format!("{} (offset 0x{:x})", end, end.offset));
+    ret.fields.append(&mut reader.fields);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{dump_structure, Section};
+    use crate::{
+        data::History,
+        data_bytes::{FORMAT_VERSION_V3, MAX_DECOMPRESSED_SIZE},
+        test_util::history,
+        to_bytes,
+        wrapper::Wrapper,
+        Result,
+    };
+
+    fn sample() -> History {
+        history(vec![vec!["你好"], vec![], vec![]])
+    }
+
+    #[test]
+    fn v2() -> Result<()> {
+        let structure = dump_structure(&to_bytes(&sample())?);
+        assert_eq!(structure.error, None);
+        assert_eq!(
+            structure.to_string(),
+            "\
+# header
+00000000  00 0f c3 15              magic               0x000fc315
+00000004  00 00 00 02              version             2
+# pools
+00000008  00 00 00 01              pool 0              1 sentence(s)
+0000000c  00 00 00 01              sentence 0:0        1 word(s)
+00000010  00 00 00 06              word 0:0:0 length   6 byte(s)
+00000014  e4 bd a0 e5 a5 bd        word 0:0:0          \"你好\"
+0000001a  00 00 00 00              pool 1              0 sentence(s)
+0000001e  00 00 00 00              pool 2              0 sentence(s)
+"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v3() -> Result<()> {
+        let v3 = History {
+            format_version: FORMAT_VERSION_V3,
+            ..sample()
+        };
+        let structure = dump_structure(&to_bytes(&v3)?);
+        assert_eq!(structure.error, None);
+        let sections: Vec<(Section, usize, &str)> = structure
+            .fields
+            .iter()
+            .map(|field| (field.section, field.offset, field.name.as_str()))
+            .collect();
+        assert_eq!(sections[1], (Section::Header, 4, "version"));
+        assert_eq!(sections[2], (Section::Compressed, 8, "compressed"));
+        assert_eq!(sections[3], (Section::Decompressed, 0, "pool 0"));
+        assert_eq!(
+            sections.last(),
+            Some(&(Section::Decompressed, 22, "pool 2"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn too_large() -> Result<()> {
+        let mut bomb = to_bytes(&sample())?[..4].to_vec();
+        bomb.extend(FORMAT_VERSION_V3.to_be_bytes());
+        bomb.extend(zstd::encode_all(
+            &vec![0; MAX_DECOMPRESSED_SIZE + 1][..],
+            19,
+        )?);
+        let structure = dump_structure(&bomb);
+        assert_eq!(
+            structure.error,
+            Some(format!(
+                "zstd decompression failed: pools decompress to more than {} bytes",
+                MAX_DECOMPRESSED_SIZE
+            ))
+        );
+        assert_eq!(
+            structure.fields.last().unwrap().section,
+            Section::Compressed
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn wrapped() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+        let structure = dump_structure(&Wrapper::Zstd.wrap(&bytes)?);
+        assert_eq!(structure.wrapper, Some(Wrapper::Zstd));
+        assert_eq!(structure.fields, dump_structure(&bytes).fields);
+        assert!(structure.to_string().starts_with(
+            "# unwrapped from zstd, offsets are relative to the unwrapped data\n# header\n"
+        ));
+
+        let structure = dump_structure(&[0x1f, 0x8b, 0]);
+        assert_eq!(structure.wrapper, Some(Wrapper::Gzip));
+        assert!(structure
+            .error
+            .is_some_and(|error| error.starts_with("unwrapping failed: ")));
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+        let structure = dump_structure(&bytes[..0x17]);
+        assert_eq!(structure.fields.len(), 5);
+        assert_eq!(
+            structure.error.as_deref(),
+            Some("data ends inside word 0:0:0 (offset 0x14)")
+        );
+        assert!(structure
+            .to_string()
+            .ends_with("# error: data ends inside word 0:0:0 (offset 0x14)\n"));
+
+        let structure = dump_structure(&bytes[..0x1e]);
+        assert_eq!(
+            structure.error.as_deref(),
+            Some("data ends after 2 pool(s), libime expects 3 (offset 0x1e)")
+        );
+
+        let mut trailing = bytes;
+        trailing.extend([0, 0, 0, 0, 0]);
+        let structure = dump_structure(&trailing);
+        assert_eq!(structure.error, None);
+        assert!(structure.to_string().ends_with(
+            "0000001e  00 00 00 00              pool 2              0 sentence(s)\n\
+             00000022  00 00 00 00 00           trailing data       5 byte(s) after the last pool\n"
+        ));
+
+        let structure = dump_structure(b"text");
+        assert_eq!(
+            structure.error.as_deref(),
+            Some("invalid magic, not a binary history")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::BTreeSet;
+
+use libime_history_merge::{
+    data::History,
+    search::{Location, Pattern, Scope},
+    Result,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Paragraph, Tabs},
+    DefaultTerminal, Frame,
+};
+
+const HELP: &str = "←/→ pool  ↑/↓ move  / search  space select  d delete  t to newest  u undo  \
+                    s save  q quit";
+
+/// Number of sentences PageUp/PageDown move the cursor by.
+const PAGE: isize = 20;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mode {
+    Normal,
+    /// Typing a search query
+    Search,
+}
+
+/// What the event loop should do after a key is handled.
+#[derive(Debug, Eq, PartialEq)]
+enum Action {
+    None,
+    Save,
+    Quit,
+}
+
+/// State of the browser, kept apart from the terminal so that it can be tested.
+struct Browser {
+    history: History,
+    /// The history as it was last saved
+    saved: History,
+    /// Histories before each change, the last one being the most recent
+    undo: Vec<History>,
+    /// Index of the pool shown
+    tab: usize,
+    /// Indices, inside the pool shown, of the sentences matching the query
+    view: Vec<usize>,
+    /// Index inside `view` of the sentence under the cursor
+    cursor: usize,
+    selected: BTreeSet<Location>,
+    query: String,
+    mode: Mode,
+    message: Option<String>,
+    /// Whether quitting with unsaved changes has been asked for once
+    quitting: bool,
+}
+
+impl Browser {
+    fn new(history: History) -> Self {
+        let mut ret = Browser {
+            saved: history.clone(),
+            history,
+            undo: Vec::new(),
+            tab: 0,
+            view: Vec::new(),
+            cursor: 0,
+            selected: BTreeSet::new(),
+            query: String::new(),
+            mode: Mode::Normal,
+            message: None,
+            quitting: false,
+        };
+        ret.refresh();
+        ret
+    }
+
+    fn is_modified(&self) -> bool {
+        self.history != self.saved
+    }
+
+    /// Recomputes the sentences shown, keeping the cursor inside them.
+    fn refresh(&mut self) {
+        self.tab = self.tab.min(self.history.pools.len().saturating_sub(1));
+        let pattern = Pattern::literal(&self.query, Scope::Sentence);
+        self.view = match self.history.pools.get(self.tab) {
+            Some(pool) => pool
+                .0
+                .iter()
+                .enumerate()
+                .filter(|(_, sentence)| self.query.is_empty() || pattern.is_match(sentence))
+                .map(|(i, _)| i)
+                .collect(),
+            None => Vec::new(),
+        };
+        self.cursor = self.cursor.min(self.view.len().saturating_sub(1));
+    }
+
+    fn switch_tab(&mut self, tab: usize) {
+        if tab < self.history.pools.len() && tab != self.tab {
+            self.tab = tab;
+            self.cursor = 0;
+            self.refresh();
+        }
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let last = self.view.len().saturating_sub(1) as isize;
+        self.cursor = (self.cursor as isize + delta).clamp(0, last) as usize;
+    }
+
+    fn current(&self) -> Option<Location> {
+        self.view.get(self.cursor).map(|&index| Location {
+            pool: self.tab,
+            index,
+        })
+    }
+
+    fn toggle_selection(&mut self) {
+        if let Some(location) = self.current() {
+            if !self.selected.remove(&location) {
+                self.selected.insert(location);
+            }
+            self.move_cursor(1);
+        }
+    }
+
+    /// The selected sentences, or the one under the cursor if none is selected.
+    fn targets(&self) -> Vec<Location> {
+        match self.selected.is_empty() {
+            true => self.current().into_iter().collect(),
+            false => self.selected.iter().copied().collect(),
+        }
+    }
+
+    /// Runs `change` on the history, making it undoable.
+    fn change<F>(&mut self, change: F)
+    where
+        F: FnOnce(&mut History),
+    {
+        self.undo.push(self.history.clone());
+        change(&mut self.history);
+        self.selected.clear();
+        self.refresh();
+    }
+
+    fn delete(&mut self) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        self.change(|history| {
+            for location in targets.iter().rev() {
+                history.pools[location.pool].0.remove(location.index);
+            }
+        });
+        self.message = Some(format!("Deleted {} sentence(s)", targets.len()));
+    }
+
+    fn move_to_newest(&mut self) {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return;
+        }
+        let mut dropped = Vec::new();
+        self.change(|history| dropped = history.move_to_newest(&targets));
+        self.tab = 0;
+        self.cursor = 0;
+        self.refresh();
+        self.message = Some(match dropped.len() {
+            0 => format!("Moved {} sentence(s) to the newest position", targets.len()),
+            n => format!(
+                "Moved {} sentence(s) to the newest position, {} oldest sentence(s) no longer \
+                 fit and were dropped",
+                targets.len(),
+                n
+            ),
+        });
+    }
+
+    fn undo(&mut self) {
+        match self.undo.pop() {
+            Some(history) => {
+                self.history = history;
+                self.selected.clear();
+                self.refresh();
+                self.message = Some("Undone".to_string());
+            }
+            None => self.message = Some("Nothing to undo".to_string()),
+        }
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Action {
+        if key.kind != KeyEventKind::Press {
+            return Action::None;
+        }
+        let quitting = std::mem::take(&mut self.quitting);
+        self.message = None;
+        if self.mode == Mode::Search {
+            match key.code {
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    self.query.clear();
+                    self.refresh();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.refresh();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.cursor = 0;
+                    self.refresh();
+                }
+                _ => {}
+            }
+            return Action::None;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Action::Quit
+            }
+            KeyCode::Char('q') | KeyCode::Esc if quitting || !self.is_modified() => {
+                return Action::Quit
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quitting = true;
+                self.message = Some(
+                    "There are unsaved changes, press q again to discard them, or s to save"
+                        .to_string(),
+                );
+            }
+            KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => self.switch_tab(self.tab + 1),
+            KeyCode::Left | KeyCode::BackTab | KeyCode::Char('h') => {
+                self.switch_tab(self.tab.saturating_sub(1))
+            }
+            KeyCode::Char(c @ '1'..='9') => self.switch_tab(c as usize - '1' as usize),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::PageDown => self.move_cursor(PAGE),
+            KeyCode::PageUp => self.move_cursor(-PAGE),
+            KeyCode::Home | KeyCode::Char('g') => self.cursor = 0,
+            KeyCode::End | KeyCode::Char('G') => self.move_cursor(isize::MAX / 2),
+            KeyCode::Char('/') => {
+                self.mode = Mode::Search;
+                self.query.clear();
+                self.cursor = 0;
+                self.refresh();
+            }
+            KeyCode::Char(' ') => self.toggle_selection(),
+            KeyCode::Char('d') | KeyCode::Delete => self.delete(),
+            KeyCode::Char('t') => self.move_to_newest(),
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('s') => return Action::Save,
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn draw(&self, frame: &mut Frame, title: &str) {
+        let [tabs, list, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Tabs::new(
+                self.history
+                    .pools
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pool)| format!("pool {} ({})", i, pool.0.len())),
+            )
+            .select(self.tab)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            tabs,
+        );
+
+        let sentences = self
+            .history
+            .pools
+            .get(self.tab)
+            .map_or(&[][..], |pool| &pool.0);
+        let items = self.view.iter().map(|&index| {
+            let mark = match self.selected.contains(&Location {
+                pool: self.tab,
+                index,
+            }) {
+                true => '*',
+                false => ' ',
+            };
+            ListItem::new(format!("{} {:>5} {}", mark, index, sentences[index]))
+        });
+        let title = format!(
+            "{}{} ({} shown, {} selected)",
+            title,
+            if self.is_modified() {
+                " [modified]"
+            } else {
+                ""
+            },
+            self.view.len(),
+            self.selected.len(),
+        );
+        let mut state = ListState::default().with_selected(Some(self.cursor));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut state,
+        );
+
+        let line = match (self.mode, &self.message) {
+            (Mode::Search, _) => format!("/{}", self.query),
+            (Mode::Normal, Some(message)) => message.clone(),
+            (Mode::Normal, None) if !self.query.is_empty() => {
+                format!("Showing matches of \"{}\"  {}", self.query, HELP)
+            }
+            (Mode::Normal, None) => HELP.to_string(),
+        };
+        frame.render_widget(Paragraph::new(Line::from(line)), status);
+    }
+}
+
+/// Browses `history` in the terminal, calling `save` with the edited history whenever the user
+/// asks to save it.  `save` returns the message shown in the status line on success.  `title` is
+/// shown above the sentences.
+pub fn browse<F>(history: History, title: &str, mut save: F) -> Result<()>
+where
+    F: FnMut(&History) -> Result<String>,
+{
+    let mut terminal = ratatui::init();
+    let ret = run(&mut terminal, Browser::new(history), title, &mut save);
+    ratatui::restore();
+    ret
+}
+
+fn run(
+    terminal: &mut DefaultTerminal,
+    mut browser: Browser,
+    title: &str,
+    save: &mut dyn FnMut(&History) -> Result<String>,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| browser.draw(frame, title))?;
+        let key = match event::read()? {
+            Event::Key(key) => key,
+            _ => continue,
+        };
+        match browser.handle(key) {
+            Action::None => {}
+            Action::Save => {
+                browser.message = Some(match save(&browser.history) {
+                    Ok(message) => {
+                        browser.saved = browser.history.clone();
+                        message
+                    }
+                    Err(e) => format!("Could not save: {}", e),
+                });
+            }
+            Action::Quit => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+    use super::{Action, Browser};
+    use libime_history_merge::data::{History, Pool, Sentence, Word};
+
+    // The library's `test_util` is only compiled into the library's own tests
+    fn sentence(content: &str) -> Sentence {
+        Sentence(content.split(' ').map(String::from).map(Word).collect())
+    }
+
+    fn browser() -> Browser {
+        Browser::new(History::new(vec![
+            Pool(vec![
+                sentence("你好"),
+                sentence("飞书 文档"),
+                sentence("再见"),
+            ]),
+            Pool(vec![sentence("打开 飞书"), sentence("好 音乐")]),
+        ]))
+    }
+
+    fn press(browser: &mut Browser, keys: &str) -> Action {
+        let mut ret = Action::None;
+        for c in keys.chars() {
+            ret = browser.handle(KeyEvent::from(KeyCode::Char(c)));
+        }
+        ret
+    }
+
+    #[test]
+    fn search() {
+        let mut browser = browser();
+        press(&mut browser, "/飞书");
+        assert_eq!(browser.view, vec![1]);
+        browser.handle(KeyEvent::from(KeyCode::Enter));
+        press(&mut browser, "l");
+        assert_eq!(browser.tab, 1);
+        assert_eq!(browser.view, vec![0]);
+        browser.handle(KeyEvent::from(KeyCode::Char('/')));
+        assert_eq!(browser.view, vec![0, 1]);
+    }
+
+    #[test]
+    fn delete_and_undo() {
+        let mut browser = browser();
+        press(&mut browser, " j ");
+        assert_eq!(browser.cursor, 2);
+        press(&mut browser, "d");
+        assert_eq!(
+            browser.history,
+            History::new(vec![
+                Pool(vec![sentence("飞书 文档")]),
+                Pool(vec![sentence("打开 飞书"), sentence("好 音乐")]),
+            ])
+        );
+        assert_eq!(browser.cursor, 0);
+        assert!(browser.is_modified());
+        press(&mut browser, "d");
+        assert!(browser.history.pools[0].0.is_empty());
+        press(&mut browser, "uu");
+        assert!(!browser.is_modified());
+        assert_eq!(browser.message.as_deref(), Some("Undone"));
+        press(&mut browser, "u");
+        assert_eq!(browser.message.as_deref(), Some("Nothing to undo"));
+    }
+
+    #[test]
+    fn move_to_newest() {
+        let mut browser = browser();
+        press(&mut browser, "2j t");
+        assert_eq!(browser.tab, 0);
+        assert_eq!(
+            browser.history,
+            History::new(vec![
+                Pool(vec![
+                    sentence("好 音乐"),
+                    sentence("你好"),
+                    sentence("飞书 文档"),
+                    sentence("再见"),
+                ]),
+                Pool(vec![sentence("打开 飞书")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn quit() {
+        let mut browser = browser();
+        assert_eq!(press(&mut browser, "q"), Action::Quit);
+        press(&mut browser, "d");
+        assert_eq!(press(&mut browser, "q"), Action::None);
+        assert_eq!(press(&mut browser, "q"), Action::Quit);
+        assert_eq!(press(&mut browser, "s"), Action::Save);
+    }
+}
@@ -1,3 +1,7 @@
+use std::path::PathBuf;
+
+use crate::{Error, Result};
+
 pub(crate) fn gcd(a: u8, b: u8) -> u8 {
     match b {
         0 => a,
@@ -24,5 +28,42 @@ pub(crate) fn split_vec<T: Clone>(v: Vec<T>, sizes: &[usize]) -> Vec<Vec<T>> {
     ret
 }
 
+/// Resolves an XDG base directory from the environment variable `var`, falling back to
+/// `$HOME/<fallback>` when it is unset, empty or not absolute.
+///
+/// REF: <https://specifications.freedesktop.org/basedir-spec/latest/>
+pub(crate) fn xdg_dir(var: &str, fallback: &str) -> Result<PathBuf> {
+    match std::env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => match std::env::var_os("HOME") {
+            Some(home) if !home.is_empty() => Ok(PathBuf::from(home).join(fallback)),
+            _ => Err(Error::IoError(format!("Neither ${} nor $HOME is set", var))),
+        },
+    }
+}
+
+/// Formats the current time in UTC as `YYYYmmddTHHMMSSZ`, which sorts lexicographically in
+/// chronological order, also across daylight saving time changes, and is safe to embed in file
+/// names.
+pub(crate) fn timestamp() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default() as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::gmtime_r(&now, &mut tm);
+    }
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec,
+    )
+}
+
 // Author: Blurgy <gy@blurgy.xyz>
 // Date:   Feb 05 2022, 15:41 [CST]
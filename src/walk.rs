@@ -0,0 +1,130 @@
+use std::fmt::Display;
+
+use crate::merging::POOL_SIZE;
+
+/// A length-prefixed field of the pools of a binary history.  Pools, sentences and words are
+/// numbered in the order they are stored (sentences oldest first).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Field {
+    /// Number of sentences of a pool
+    Pool(usize),
+    /// Number of words of a sentence of a pool
+    Sentence(usize, usize),
+    /// Length of a word of a sentence of a pool
+    WordLength(usize, usize, usize),
+    /// Bytes of a word of a sentence of a pool
+    Word(usize, usize, usize),
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Pool(p) => write!(f, "pool {}", p),
+            Field::Sentence(p, s) => write!(f, "sentence {}:{}", p, s),
+            Field::WordLength(p, s, w) => write!(f, "word {}:{}:{} length", p, s, w),
+            Field::Word(p, s, w) => write!(f, "word {}:{}:{}", p, s, w),
+        }
+    }
+}
+
+/// Where the data of a binary history ends before its last pool does, see [`walk`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct End {
+    /// The field that could not be read
+    pub field: Field,
+    /// Offset of the field, see [`Source::offset`]
+    pub offset: u64,
+}
+
+impl Display for End {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.field {
+            Field::Pool(p) => write!(
+                f,
+                "data ends after {} pool(s), libime expects {}",
+                p,
+                POOL_SIZE.len()
+            ),
+            field => write!(f, "data ends inside {}", field),
+        }
+    }
+}
+
+/// Pools of a binary history, read by [`walk`].
+pub(crate) trait Source {
+    /// Offset of the next field.
+    fn offset(&self) -> u64;
+
+    /// Reads the big-endian u32 `field`, or returns `None` if the data ends first.
+    fn u32(&mut self, field: Field) -> std::io::Result<Option<u32>>;
+
+    /// Reads or passes over the `len` bytes of `field`, or returns `false` if the data ends
+    /// first.
+    fn bytes(&mut self, field: Field, len: u32) -> std::io::Result<bool>;
+
+    /// Passes over the data left after the last pool, returns its size in bytes.
+    fn rest(&mut self) -> std::io::Result<u64>;
+}
+
+/// What [`walk`] found.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Walk {
+    /// Number of sentences of each pool read completely
+    pub pools: Vec<usize>,
+    /// Where the data ends, if it ends before the last pool does
+    pub end: Option<End>,
+    /// Number of bytes left after the last pool
+    pub trailing: u64,
+}
+
+/// Why [`walk`] stops before the end of the last pool.
+enum Stop {
+    End(End),
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for Stop {
+    fn from(e: std::io::Error) -> Self {
+        Stop::Io(e)
+    }
+}
+
+/// Reads the big-endian u32 `field` from `source`.
+fn read_u32<S: Source>(source: &mut S, field: Field) -> Result<u32, Stop> {
+    let offset = source.offset();
+    source.u32(field)?.ok_or(Stop::End(End { field, offset }))
+}
+
+/// Reads the pools into `pools`, the number of sentences of each of them.
+fn read_pools<S: Source>(source: &mut S, pools: &mut Vec<usize>) -> Result<(), Stop> {
+    for p in 0..POOL_SIZE.len() {
+        let sentences = read_u32(source, Field::Pool(p))?;
+        for s in 0..sentences as usize {
+            let words = read_u32(source, Field::Sentence(p, s))?;
+            for w in 0..words as usize {
+                let len = read_u32(source, Field::WordLength(p, s, w))?;
+                let field = Field::Word(p, s, w);
+                let offset = source.offset();
+                if !source.bytes(field, len)? {
+                    return Err(Stop::End(End { field, offset }));
+                }
+            }
+        }
+        pools.push(sentences as usize);
+    }
+    Ok(())
+}
+
+/// Follows the length prefixes of [`libime`][libime]'s pools from `source`, reading exactly as
+/// many pools as libime does.  Whatever comes after the last pool is trailing data.
+///
+/// [libime]: https://github.com/fcitx/libime
+pub(crate) fn walk<S: Source>(source: &mut S) -> std::io::Result<Walk> {
+    let mut ret = Walk::default();
+    match read_pools(source, &mut ret.pools) {
+        Ok(()) => ret.trailing = source.rest()?,
+        Err(Stop::End(end)) => ret.end = Some(end),
+        Err(Stop::Io(e)) => return Err(e),
+    }
+    Ok(ret)
+}
@@ -0,0 +1,195 @@
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    io::{Read, Write},
+    path::Path,
+};
+
+use crate::{data_bytes::MAX_DECOMPRESSED_SIZE, Error, Result};
+
+/// General-purpose compression a history file may be wrapped in, e.g. by backup tools.  Not to
+/// be confused with the compression of the pools of version 3 histories.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Wrapper {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Display for Wrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Wrapper::Gzip => "gzip",
+            Wrapper::Xz => "xz",
+            Wrapper::Zstd => "zstd",
+        })
+    }
+}
+
+impl Wrapper {
+    const ALL: [Wrapper; 3] = [Wrapper::Gzip, Wrapper::Xz, Wrapper::Zstd];
+
+    /// Magic bytes a wrapped file begins with.
+    pub fn magic(self) -> &'static [u8] {
+        match self {
+            Wrapper::Gzip => &[0x1f, 0x8b],
+            Wrapper::Xz => &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+            Wrapper::Zstd => &[0x28, 0xb5, 0x2f, 0xfd],
+        }
+    }
+
+    /// File name extension of wrapped files, without the leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Wrapper::Gzip => "gz",
+            Wrapper::Xz => "xz",
+            Wrapper::Zstd => "zst",
+        }
+    }
+
+    /// Finds the wrapper `content` is in by its magic bytes.
+    pub fn detect(content: &[u8]) -> Option<Self> {
+        Wrapper::ALL
+            .into_iter()
+            .find(|wrapper| content.starts_with(wrapper.magic()))
+    }
+
+    /// Finds the wrapper a file at `path` should be in by the extension of its name, e.g.
+    /// `user.history.gz`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Wrapper::ALL.into_iter().find(|wrapper| {
+            wrapper.extension() == extension || (*wrapper == Wrapper::Zstd && extension == "zstd")
+        })
+    }
+
+    /// Decompresses `content`, failing if it decompresses to more than
+    /// [`MAX_DECOMPRESSED_SIZE`] bytes, the same limit as for the pools of version 3 histories.
+    pub fn unwrap(self, content: &[u8]) -> Result<Vec<u8>> {
+        let decoder: Box<dyn Read + '_> = match self {
+            Wrapper::Gzip => Box::new(flate2::read::MultiGzDecoder::new(content)),
+            Wrapper::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(content)),
+            Wrapper::Zstd => Box::new(zstd::Decoder::new(content)?),
+        };
+        let mut ret = Vec::new();
+        decoder
+            .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+            .read_to_end(&mut ret)?;
+        if ret.len() > MAX_DECOMPRESSED_SIZE {
+            return Err(Error::DeserializeError(format!(
+                "{} compressed content is larger than {} bytes",
+                self, MAX_DECOMPRESSED_SIZE
+            )));
+        }
+        Ok(ret)
+    }
+
+    /// Compresses `content`.
+    pub fn wrap(self, content: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            Wrapper::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(content)?;
+                encoder.finish()?
+            }
+            Wrapper::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(content)?;
+                encoder.finish()?
+            }
+            Wrapper::Zstd => zstd::encode_all(content, zstd::DEFAULT_COMPRESSION_LEVEL)?,
+        })
+    }
+}
+
+/// Decompresses `content` if it is wrapped, see [`Wrapper::detect`].
+pub(crate) fn unwrap(content: &[u8]) -> Result<Cow<'_, [u8]>> {
+    match Wrapper::detect(content) {
+        Some(wrapper) => {
+            log::debug!("unwrapping {} compressed content", wrapper);
+            Ok(Cow::Owned(wrapper.unwrap(content)?))
+        }
+        None => Ok(Cow::Borrowed(content)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+
+    use super::Wrapper;
+    use crate::{
+        data::History, data_bytes::MAX_DECOMPRESSED_SIZE, test_util::history, to_bytes, Error,
+        Result,
+    };
+
+    fn sample() -> History {
+        history(vec![vec!["音乐 好听", "你好"], vec![], vec![]])
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let bytes = to_bytes(&sample())?;
+        for wrapper in Wrapper::ALL {
+            let wrapped = wrapper.wrap(&bytes)?;
+            assert_eq!(Wrapper::detect(&wrapped), Some(wrapper));
+            assert_eq!(wrapper.unwrap(&wrapped)?, bytes);
+            assert_eq!(History::parse(&wrapped)?, sample());
+        }
+        assert_eq!(Wrapper::detect(&bytes), None);
+        assert!(Wrapper::Gzip.unwrap(&[0x1f, 0x8b, 0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn size_limit() -> Result<()> {
+        let zeros = vec![0; MAX_DECOMPRESSED_SIZE + 1];
+        for wrapper in Wrapper::ALL {
+            assert_eq!(
+                wrapper.unwrap(&wrapper.wrap(&zeros)?),
+                Err(Error::DeserializeError(format!(
+                    "{} compressed content is larger than {} bytes",
+                    wrapper, MAX_DECOMPRESSED_SIZE
+                )))
+            );
+            assert_eq!(
+                wrapper.unwrap(&wrapper.wrap(&zeros[1..])?)?.len(),
+                MAX_DECOMPRESSED_SIZE
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn from_path() {
+        let wrapper = |path: &str| Wrapper::from_path(Path::new(path));
+        assert_eq!(wrapper("user.history.gz"), Some(Wrapper::Gzip));
+        assert_eq!(wrapper("user.history.xz"), Some(Wrapper::Xz));
+        assert_eq!(wrapper("user.history.zst"), Some(Wrapper::Zstd));
+        assert_eq!(wrapper("user.history.zstd"), Some(Wrapper::Zstd));
+        assert_eq!(wrapper("user.history"), None);
+        assert_eq!(wrapper("gz"), None);
+    }
+
+    #[test]
+    fn save_and_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for wrapper in Wrapper::ALL {
+            let path = dir
+                .path()
+                .join(format!("user.history.{}", wrapper.extension()));
+            sample().save(&path)?;
+            assert_eq!(
+                Wrapper::detect(&std::fs::read(&path)?),
+                Some(wrapper),
+                "{}",
+                wrapper
+            );
+            assert_eq!(History::load(&path)?, sample());
+        }
+        Ok(())
+    }
+}